@@ -0,0 +1,6 @@
+//! The syntax tree produced by [`parse_string`](crate::parse_string).
+
+pub use crate::lexer::{NumType, Number, Span};
+pub use crate::parser::{
+    Expr, ExprKind, Field, ImportName, Param, Program, Stat, StatKind, Type, TypeKind,
+};
@@ -1,27 +1,279 @@
 use std::{
-    fs::File,
-    io::{self, Read},
+    fs::{self, File},
+    io::{self, IsTerminal, Read, Write},
+    path::Path,
+    process,
 };
 
-use eretria::{lexer::Token, parse_string};
-use logos::Logos;
+use eretria::{
+    check_sources,
+    diagnostics::{Diagnostic, Label},
+    format_string, is_incomplete,
+    lexer::{tokenize, Token},
+    parse_partial, parse_string, Program, Source, Span, StatKind,
+};
+use line_col::LineColLookup;
+use serde::Serialize;
 
 extern crate clap;
 use clap::{App, Arg, SubCommand};
 
 fn input_arg<'a, 'b>() -> Arg<'a, 'b> {
     Arg::with_name("INPUT")
-        .help("Sets the input file to use")
+        .help("Sets the input file to use, `-` for stdin")
+        .required(true)
+}
+
+fn inputs_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("INPUT")
+        .help("Sets the input files, compiled as one module, `-` for stdin")
         .required(true)
+        .multiple(true)
+}
+
+fn format_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("format")
+        .long("format")
+        .help("Sets the output format")
+        .takes_value(true)
+        .possible_values(&["text", "json"])
+        .default_value("text")
+}
+
+#[derive(Serialize)]
+struct SpannedToken {
+    token: Token,
+    span: Span,
+}
+
+#[derive(Serialize)]
+struct LexOutput {
+    tokens: Vec<SpannedToken>,
+    errors: Vec<Diagnostic>,
+}
+
+#[derive(Serialize)]
+struct ParseOutput {
+    program: Program,
+    errors: Vec<Diagnostic>,
+}
+
+#[derive(Serialize)]
+struct FileDiagnostics<'a> {
+    name: &'a str,
+    errors: Vec<Diagnostic>,
+}
+
+#[derive(Serialize)]
+struct CheckOutput<'a> {
+    files: Vec<FileDiagnostics<'a>>,
+}
+
+/// Prints `output` as a line of JSON, exiting with a failure code if
+/// there were errors.
+fn print_json(output: &impl Serialize, failed: bool) -> io::Result<()> {
+    println!("{}", serde_json::to_string(output)?);
+    if failed {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// Lists every token with its line and column range, one per line, as
+/// printed by `lex` and `build --emit tokens`.
+fn token_listing(buf: &str) -> (String, Vec<Diagnostic>) {
+    let lookup = LineColLookup::new(buf);
+    let mut listing = String::new();
+    let mut errors = Vec::new();
+    for tok in tokenize(buf) {
+        match tok {
+            Ok((tok, span)) => {
+                let (start_line, start_col) = lookup.get(span.start);
+                let (end_line, end_col) = lookup.get(span.end);
+                listing.push_str(&format!(
+                    "{}:{}-{}:{}\t{}\n",
+                    start_line, start_col, end_line, end_col, tok
+                ));
+            }
+            Err(e) => errors.push(e.into()),
+        }
+    }
+    (listing, errors)
+}
+
+/// Writes `bytes` to the file at `output`, or to stdout if it is `-`.
+fn write_output(output: &str, bytes: &[u8]) -> io::Result<()> {
+    if output == "-" {
+        let mut stdout = io::stdout();
+        stdout.write_all(bytes)?;
+        stdout.flush()
+    } else {
+        fs::write(output, bytes)
+    }
 }
 
 fn file_to_string(input: impl AsRef<str>) -> io::Result<String> {
+    if input.as_ref() == "-" {
+        let mut buf = String::new();
+        io::stdin().read_to_string(&mut buf)?;
+        return Ok(buf);
+    }
+
     let mut file = File::open(input.as_ref())?;
     let mut buf = String::with_capacity(file.metadata()?.len() as usize);
     file.read_to_string(&mut buf)?;
     Ok(buf)
 }
 
+/// The name diagnostics use for an input.
+fn display_name(input: &str) -> &str {
+    if input == "-" {
+        "<stdin>"
+    } else {
+        input
+    }
+}
+
+fn read_sources(inputs: clap::Values) -> io::Result<Vec<Source>> {
+    inputs
+        .map(|input| {
+            Ok(Source {
+                name: display_name(input).to_owned(),
+                text: file_to_string(input)?,
+            })
+        })
+        .collect()
+}
+
+/// Checks the inputs along with the files they `use`, see [`check_sources`].
+fn check(sources: Vec<Source>) -> (Vec<Source>, Vec<Vec<Diagnostic>>) {
+    check_sources(sources, |path| fs::read_to_string(path))
+}
+
+/// Renders every diagnostic to stderr and exits with a failure code.
+fn report(errors: Vec<Diagnostic>, source: &str, name: &str) -> ! {
+    let color = io::stderr().is_terminal();
+    for e in errors {
+        eprint!("{}", e.render(source, display_name(name), color));
+    }
+    process::exit(1);
+}
+
+/// Like [`report`] for the diagnostics of several sources, but only exits
+/// if there are any.
+fn report_sources(sources: &[Source], errors: Vec<Vec<Diagnostic>>) {
+    if errors.iter().all(Vec::is_empty) {
+        return;
+    }
+
+    let color = io::stderr().is_terminal();
+    for (source, errors) in sources.iter().zip(errors) {
+        for e in errors {
+            eprint!("{}", e.render(&source.text, &source.name, color));
+        }
+    }
+    process::exit(1);
+}
+
+/// Name of the function a REPL expression is checked in.
+const REPL_FN: &str = "__repl";
+/// How the repl names its input in diagnostics.
+const REPL_NAME: &str = "<repl>";
+
+/// Moves a diagnostic of the repl source onto the entry that starts at
+/// `offset` in it, so that neither earlier items nor the function wrapping
+/// an expression show up. Labels on earlier items become notes.
+fn entry_error(mut e: Diagnostic, offset: usize, entry: &str) -> Diagnostic {
+    let shift = |span: &Span| {
+        let start = span.start.saturating_sub(offset).min(entry.len());
+        start..span.end.saturating_sub(offset).clamp(start, entry.len())
+    };
+    e.span = shift(&e.span);
+    for label in std::mem::take(&mut e.labels) {
+        if label.span.start < offset {
+            e.notes
+                .push(format!("{} in an earlier entry", label.message));
+        } else {
+            e.labels.push(Label {
+                span: shift(&label.span),
+                message: label.message,
+            });
+        }
+    }
+    e
+}
+
+/// Reads items and expressions line by line until the end of stdin. Items
+/// are kept for the rest of the session once they check, a line that
+/// leaves a `{` or `(` open continues on the next.
+fn repl() -> io::Result<()> {
+    let interactive = io::stdin().is_terminal();
+    let color = io::stderr().is_terminal();
+    let mut items = String::new();
+    let mut input = String::new();
+    loop {
+        if interactive {
+            print!("{}", if input.is_empty() { "> " } else { ". " });
+            io::stdout().flush()?;
+        }
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        input.push_str(&line);
+        if input.trim().is_empty() {
+            input.clear();
+            continue;
+        }
+        if is_incomplete(&input) {
+            continue;
+        }
+
+        let entry = std::mem::take(&mut input);
+        let item = matches!(
+            tokenize(&entry).next(),
+            Some(Ok((Token::Fn, _)))
+                | Some(Ok((Token::Data, _)))
+                | Some(Ok((Token::Use, _)))
+                | Some(Ok((Token::Struct, _)))
+                | Some(Ok((Token::DocComment(_), _)))
+        );
+        let prefix = if item {
+            String::new()
+        } else {
+            format!("fn {}() ", REPL_FN)
+        };
+        let offset = items.len() + prefix.len();
+        let source = format!("{}{}{}", items, prefix, entry);
+
+        let root = Source {
+            name: REPL_NAME.to_owned(),
+            text: source.clone(),
+        };
+        let (sources, errors) = check(vec![root]);
+        if errors.iter().any(|errors| !errors.is_empty()) {
+            for (file, errors) in errors.into_iter().enumerate() {
+                for e in errors {
+                    if file == 0 {
+                        eprint!(
+                            "{}",
+                            entry_error(e, offset, &entry).render(&entry, REPL_NAME, color)
+                        );
+                    } else {
+                        let source = &sources[file];
+                        eprint!("{}", e.render(&source.text, &source.name, color));
+                    }
+                }
+            }
+        } else if item {
+            items = source;
+        } else {
+            // TODO: compile and interpret once the compiler generates code
+            eprintln!("error: cannot evaluate: code generation is not implemented yet");
+        }
+    }
+}
+
 fn main() -> io::Result<()> {
     let matches = App::new("Eretria")
         .version("0.0.1")
@@ -30,17 +282,71 @@ fn main() -> io::Result<()> {
         .subcommand(
             SubCommand::with_name("lex")
                 .about("Dumps all tokens")
-                .arg(input_arg()),
+                .arg(input_arg())
+                .arg(format_arg()),
         )
         .subcommand(
             SubCommand::with_name("parse")
                 .about("Dumps the AST")
-                .arg(input_arg()),
+                .arg(input_arg())
+                .arg(format_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("fmt")
+                .about("Formats the source file in place, or to stdout if it is `-`")
+                .arg(input_arg())
+                .arg(
+                    Arg::with_name("check")
+                        .long("check")
+                        .help("Fails instead of writing if the file isn't formatted"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("check")
+                .about("Reports errors without building")
+                .arg(inputs_arg())
+                .arg(format_arg()),
         )
         .subcommand(
             SubCommand::with_name("build")
                 .about("Builds the program")
-                .arg(input_arg()),
+                .arg(inputs_arg())
+                .arg(
+                    Arg::with_name("emit")
+                        .long("emit")
+                        .help("Sets what to output")
+                        .takes_value(true)
+                        .possible_values(&["wasm", "wat", "tokens", "ast", "ir"])
+                        .default_value("wasm"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .help("Sets the output file, `-` for stdout [default: the first INPUT with the extension of --emit]")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("run")
+                .about("Builds the program and calls one of its functions")
+                .arg(inputs_arg())
+                .arg(
+                    Arg::with_name("invoke")
+                        .long("invoke")
+                        .help("Sets the function to call")
+                        .takes_value(true)
+                        .default_value("main"),
+                )
+                .arg(
+                    Arg::with_name("ARGS")
+                        .help("Arguments passed to the function, after `--`")
+                        .multiple(true)
+                        .last(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("repl")
+                .about("Reads functions and expressions interactively"),
         )
         .get_matches();
 
@@ -48,21 +354,152 @@ fn main() -> io::Result<()> {
         ("lex", Some(matches)) => {
             let input = matches.value_of("INPUT").expect("expected an input file");
             let buf = file_to_string(input)?;
-            for tok in Token::lexer(&buf) {
-                print!("{:?}  ", tok);
+            if matches.value_of("format") == Some("json") {
+                let mut output = LexOutput {
+                    tokens: Vec::new(),
+                    errors: Vec::new(),
+                };
+                for tok in tokenize(&buf) {
+                    match tok {
+                        Ok((token, span)) => output.tokens.push(SpannedToken { token, span }),
+                        Err(e) => output.errors.push(e.into()),
+                    }
+                }
+                return print_json(&output, !output.errors.is_empty());
+            }
+
+            let (listing, errors) = token_listing(&buf);
+            print!("{}", listing);
+            if !errors.is_empty() {
+                report(errors, &buf, input);
             }
         }
         ("parse", Some(matches)) => {
             let input = matches.value_of("INPUT").expect("expected an input file");
             let buf = file_to_string(input)?;
-            print!("{:#?}", parse_string(buf).unwrap());
+            if matches.value_of("format") == Some("json") {
+                let (program, errors) = parse_partial(&buf);
+                let failed = !errors.is_empty();
+                return print_json(&ParseOutput { program, errors }, failed);
+            }
+
+            match parse_string(&buf) {
+                Ok(program) => print!("{:#?}", program),
+                Err(errors) => report(errors, &buf, input),
+            }
         }
-        ("build", Some(matches)) => {
+        ("fmt", Some(matches)) => {
             let input = matches.value_of("INPUT").expect("expected an input file");
             let buf = file_to_string(input)?;
-            parse_string(buf).unwrap();
+            let formatted = match format_string(&buf) {
+                Ok(formatted) => formatted,
+                Err(errors) => report(errors, &buf, input),
+            };
+            if matches.is_present("check") {
+                if formatted != buf {
+                    eprintln!("{} is not formatted", display_name(input));
+                    process::exit(1);
+                }
+            } else if input == "-" {
+                print!("{}", formatted);
+            } else if formatted != buf {
+                fs::write(input, formatted)?;
+            }
+        }
+        ("check", Some(matches)) => {
+            let sources = read_sources(matches.values_of("INPUT").expect("expected input files"))?;
+            let (sources, errors) = check(sources);
+            if matches.value_of("format") == Some("json") {
+                let failed = errors.iter().any(|e| !e.is_empty());
+                let files = sources
+                    .iter()
+                    .zip(errors)
+                    .map(|(source, errors)| FileDiagnostics {
+                        name: &source.name,
+                        errors,
+                    })
+                    .collect();
+                return print_json(&CheckOutput { files }, failed);
+            }
+            report_sources(&sources, errors);
+        }
+        ("build", Some(matches)) => {
+            let inputs = matches.values_of("INPUT").expect("expected input files");
+            let first = inputs.clone().next().expect("INPUT is required");
+            let sources = read_sources(inputs)?;
+            let emit = matches.value_of("emit").expect("--emit has a default");
+            let output = match matches.value_of("output") {
+                Some(output) => output.to_owned(),
+                None if first == "-" => "-".to_owned(),
+                None => Path::new(first)
+                    .with_extension(emit)
+                    .to_string_lossy()
+                    .into_owned(),
+            };
+
+            let bytes = match emit {
+                "tokens" => {
+                    let mut out = String::new();
+                    let mut errors = Vec::new();
+                    for source in &sources {
+                        let (listing, source_errors) = token_listing(&source.text);
+                        if sources.len() > 1 {
+                            out.push_str(&format!("// {}\n", source.name));
+                        }
+                        out.push_str(&listing);
+                        errors.push(source_errors);
+                    }
+                    report_sources(&sources, errors);
+                    out.into_bytes()
+                }
+                "ast" => {
+                    let parsed: Vec<_> = sources.iter().map(|s| parse_string(&s.text)).collect();
+                    let errors = parsed
+                        .iter()
+                        .map(|p| p.as_ref().err().cloned().unwrap_or_default())
+                        .collect();
+                    report_sources(&sources, errors);
+                    let program: Program = parsed.into_iter().flatten().flatten().collect();
+                    format!("{:#?}\n", program).into_bytes()
+                }
+                _ => {
+                    let (sources, errors) = check(sources);
+                    report_sources(&sources, errors);
+                    // TODO: emit once the compiler generates code
+                    eprintln!(
+                        "error: cannot emit {}: code generation is not implemented yet",
+                        emit
+                    );
+                    process::exit(1);
+                }
+            };
+            write_output(&output, &bytes)?;
+        }
+        ("run", Some(matches)) => {
+            let sources = read_sources(matches.values_of("INPUT").expect("expected input files"))?;
+            let (sources, errors) = check(sources);
+            report_sources(&sources, errors);
+
+            let invoke = matches.value_of("invoke").expect("--invoke has a default");
+            let exists = sources
+                .iter()
+                .flat_map(|s| parse_string(&s.text))
+                .flatten()
+                .any(|stat| matches!(&stat.kind, StatKind::Fn { name, .. } if name == invoke));
+            if !exists {
+                eprintln!("error: no function named `{}`", invoke);
+                process::exit(1);
+            }
+
+            // TODO: compile and interpret once the compiler generates code
+            eprintln!("error: cannot run: code generation is not implemented yet");
+            process::exit(1);
+        }
+        ("repl", Some(_)) => repl()?,
+        _ => {
+            eprintln!("expected valid subcommand");
+            process::exit(1);
         }
-        _ => eprintln!("expected valid subcommand"),
     }
     Ok(())
 }
@@ -1,9 +1,41 @@
-use crate::parser::Program;
-struct Compiler {
-    optimization_level: u8,
-    program: Program,
-}
-
-impl Compiler {
-    fn compile(&mut self) {}
-}
+use crate::{
+    diagnostics::{codes, Diagnostic, Diagnostics},
+    parser::Program,
+};
+
+/// Settings for [`compile`](crate::compile). More may be added, so start
+/// from [`Options::default`].
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct Options {
+    /// How hard to optimize, 0 for not at all.
+    pub optimization_level: u8,
+}
+
+#[allow(dead_code)]
+pub struct Compiler<'a> {
+    options: &'a Options,
+    program: Program,
+}
+
+impl<'a> Compiler<'a> {
+    pub fn new(program: Program, options: &'a Options) -> Compiler<'a> {
+        Compiler { options, program }
+    }
+
+    /// Generates the WebAssembly module of the program.
+    pub fn compile(&mut self) -> Result<Vec<u8>, Diagnostics> {
+        // TODO: generate code, then validate the module before returning it:
+        // stack types of every instruction sequence, index bounds, block
+        // types and data segments against the memory limits. A failure is
+        // an internal compiler error naming the function and source span.
+        // Field reads through a pointer lower to a load at the offset from
+        // `layout::struct_layouts`, e.g. `p.x` to `f32.load offset=0`, and
+        // assignments to fields to the matching store.
+        Err(vec![Diagnostic::error(
+            codes::UNSUPPORTED,
+            "code generation is not implemented yet",
+            0..0,
+        )])
+    }
+}
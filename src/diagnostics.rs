@@ -0,0 +1,302 @@
+use crate::lexer::Span;
+use line_col::LineColLookup;
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt};
+
+/// Error codes shared by every pass that reports diagnostics.
+pub mod codes {
+    /// A token appeared where the grammar did not allow it.
+    pub const UNEXPECTED_TOKEN: &str = "E0001";
+    /// The input ended in the middle of a construct.
+    pub const UNEXPECTED_EOF: &str = "E0002";
+    /// A `data` statement was given a negative offset.
+    pub const NEGATIVE_DATA_OFFSET: &str = "E0003";
+    /// The lexer found a character that doesn't start any token.
+    pub const UNEXPECTED_CHARACTER: &str = "E0004";
+    /// A string literal is missing its closing quote.
+    pub const UNTERMINATED_STRING: &str = "E0005";
+    /// A string escape doesn't name a valid character.
+    pub const INVALID_ESCAPE: &str = "E0006";
+    /// A number literal has a digit or prefix its radix doesn't allow.
+    pub const INVALID_DIGIT: &str = "E0007";
+    /// A number literal doesn't fit in its type.
+    pub const LITERAL_OUT_OF_RANGE: &str = "E0008";
+    /// A `/*` comment is never closed.
+    pub const UNTERMINATED_COMMENT: &str = "E0009";
+    /// A number literal ends in something that isn't a type suffix.
+    pub const INVALID_SUFFIX: &str = "E0010";
+    /// A word set aside for future syntax was used as a name.
+    pub const RESERVED_KEYWORD: &str = "E0011";
+    /// A character literal is empty or holds more than one character.
+    pub const INVALID_CHAR_LITERAL: &str = "E0012";
+    /// A name doesn't refer to any function or local in scope.
+    pub const UNRESOLVED_NAME: &str = "E0013";
+    /// Two functions share a name.
+    pub const DUPLICATE_DEFINITION: &str = "E0014";
+    /// A `use` names a file that can't be read.
+    pub const IMPORT_FAILED: &str = "E0015";
+    /// Files `use` each other in a cycle.
+    pub const IMPORT_CYCLE: &str = "E0016";
+    /// A `use` lists a function the file doesn't define.
+    pub const UNRESOLVED_IMPORT: &str = "E0017";
+    /// The program checks, but code generation can't compile it yet.
+    pub const UNSUPPORTED: &str = "E0018";
+    /// Something other than a name is assigned to, e.g. `(a, 1) = f()`, or a
+    /// tuple is assigned to a tuple of a different length.
+    pub const INVALID_ASSIGNMENT: &str = "E0019";
+    /// A type name doesn't refer to any type.
+    pub const UNKNOWN_TYPE: &str = "E0020";
+    /// A field access names a field the struct doesn't have.
+    pub const UNKNOWN_FIELD: &str = "E0021";
+    /// A field is read from something that isn't a pointer to a struct.
+    pub const INVALID_FIELD_ACCESS: &str = "E0022";
+    /// A struct holds itself by value, so it would be infinitely large.
+    pub const RECURSIVE_STRUCT: &str = "E0023";
+}
+
+/// Every diagnostic of a failed pass, in source order.
+pub type Diagnostics = Vec<Diagnostic>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// A secondary span with an explanation, e.g. where an unclosed delimiter was opened.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    pub span: Span,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(
+        severity: Severity,
+        code: &'static str,
+        message: impl Into<String>,
+        span: Span,
+    ) -> Diagnostic {
+        Diagnostic {
+            severity,
+            code,
+            message: message.into(),
+            span,
+            labels: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn error(code: &'static str, message: impl Into<String>, span: Span) -> Diagnostic {
+        Diagnostic::new(Severity::Error, code, message, span)
+    }
+
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Diagnostic {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Diagnostic {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Renders the diagnostic the way rustc does: a header, the location,
+    /// every annotated source line with underlines, then the notes.
+    /// `name` is the file name shown next to the line and column.
+    pub fn render(&self, source: &str, name: &str, color: bool) -> String {
+        let style = Style { color };
+        let lookup = LineColLookup::new(source);
+        let (line, col) = lookup.get(self.span.start.min(source.len()));
+
+        // primary annotation first so it is drawn closest to the source line
+        let mut lines: BTreeMap<usize, Vec<Annotation>> = BTreeMap::new();
+        let primary = Annotation::new(source, &lookup, &self.span, "", true);
+        lines.entry(primary.line).or_default().push(primary);
+        for label in &self.labels {
+            let annotation = Annotation::new(source, &lookup, &label.span, &label.message, false);
+            lines.entry(annotation.line).or_default().push(annotation);
+        }
+
+        let gutter = lines.keys().last().map_or(1, |l| l.to_string().len());
+        let pad = " ".repeat(gutter);
+        let bar = style.paint(Style::GUTTER, "|");
+
+        let mut out = String::new();
+        out.push_str(&format!(
+            "{}{}\n",
+            style.paint(
+                self.severity_style(),
+                &format!("{}[{}]", self.severity, self.code)
+            ),
+            style.paint(Style::BOLD, &format!(": {}", self.message)),
+        ));
+        out.push_str(&format!(
+            "{}{} {}:{}:{}\n",
+            pad,
+            style.paint(Style::GUTTER, "-->"),
+            name,
+            line,
+            col
+        ));
+        out.push_str(&format!("{} {}\n", pad, bar));
+
+        let mut last_line = None;
+        for (line, annotations) in &lines {
+            if let Some(last) = last_line {
+                if line - last > 1 {
+                    out.push_str(&format!("{}\n", style.paint(Style::GUTTER, "...")));
+                }
+            }
+            last_line = Some(*line);
+
+            let text = line_text(source, annotations[0].line_start);
+            out.push_str(&format!(
+                "{} {} {}\n",
+                style.paint(Style::GUTTER, &format!("{:>w$}", line, w = gutter)),
+                bar,
+                text
+            ));
+
+            for annotation in annotations {
+                let (marker, paint) = if annotation.primary {
+                    ('^', self.severity_style())
+                } else {
+                    ('-', Style::GUTTER)
+                };
+                let underline: String = std::iter::repeat_n(marker, annotation.width).collect();
+                let mut rendered = format!("{}{}", annotation.indent, underline);
+                if !annotation.message.is_empty() {
+                    rendered.push(' ');
+                    rendered.push_str(annotation.message);
+                }
+                out.push_str(&format!(
+                    "{} {} {}\n",
+                    pad,
+                    bar,
+                    style.paint(paint, &rendered)
+                ));
+            }
+        }
+
+        for note in &self.notes {
+            out.push_str(&format!(
+                "{} {} {}\n",
+                pad,
+                style.paint(Style::GUTTER, "="),
+                style.paint(Style::BOLD, &format!("note: {}", note))
+            ));
+        }
+
+        out
+    }
+
+    fn severity_style(&self) -> &'static str {
+        match self.severity {
+            Severity::Error => Style::ERROR,
+            Severity::Warning => Style::WARNING,
+            Severity::Note => Style::GUTTER,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.code, self.message)
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+struct Style {
+    color: bool,
+}
+
+impl Style {
+    const BOLD: &'static str = "\x1b[1m";
+    const ERROR: &'static str = "\x1b[1;31m";
+    const WARNING: &'static str = "\x1b[1;33m";
+    const GUTTER: &'static str = "\x1b[1;34m";
+
+    fn paint(&self, code: &str, s: &str) -> String {
+        if self.color {
+            format!("{}{}\x1b[0m", code, s)
+        } else {
+            s.to_owned()
+        }
+    }
+}
+
+struct Annotation<'a> {
+    line: usize,
+    line_start: usize,
+    indent: String,
+    width: usize,
+    message: &'a str,
+    primary: bool,
+}
+
+impl<'a> Annotation<'a> {
+    fn new(
+        source: &str,
+        lookup: &LineColLookup,
+        span: &Span,
+        message: &'a str,
+        primary: bool,
+    ) -> Annotation<'a> {
+        let start = span.start.min(source.len());
+        let (line, col) = lookup.get(start);
+        let line_start = start - (col - 1);
+        let text = line_text(source, line_start);
+
+        // keep tabs so the underline lines up with the source line
+        let indent = source[line_start..start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        // spans crossing a line break are cut off at the end of the line
+        let end = span.end.clamp(start, line_start + text.len());
+        let width = source[start..end].chars().count().max(1);
+
+        Annotation {
+            line,
+            line_start,
+            indent,
+            width,
+            message,
+            primary,
+        }
+    }
+}
+
+fn line_text(source: &str, line_start: usize) -> &str {
+    let rest = &source[line_start..];
+    let end = rest.find('\n').unwrap_or(rest.len());
+    rest[..end].trim_end_matches('\r')
+}
@@ -0,0 +1,139 @@
+//! How values are laid out in linear memory.
+//!
+//! Fields are stored in declaration order, each at the next offset that is
+//! a multiple of its alignment. A struct is as aligned as its most aligned
+//! field and its size is rounded up to that alignment, like C structs.
+
+use crate::{
+    lexer::NumType,
+    parser::{Field, Program, StatKind, Type, TypeKind},
+};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Size and alignment of a type, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Layout {
+    pub size: u32,
+    pub align: u32,
+}
+
+/// Pointers are 32-bit addresses.
+pub const POINTER: Layout = Layout { size: 4, align: 4 };
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldLayout {
+    pub name: String,
+    pub offset: u32,
+    pub size: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StructLayout {
+    pub size: u32,
+    pub align: u32,
+    pub fields: Vec<FieldLayout>,
+}
+
+impl NumType {
+    pub fn layout(self) -> Layout {
+        let size = match self {
+            NumType::I8 | NumType::U8 => 1,
+            NumType::I16 | NumType::U16 => 2,
+            NumType::I32 | NumType::U32 | NumType::F32 => 4,
+            NumType::I64 | NumType::U64 | NumType::F64 => 8,
+        };
+        Layout { size, align: size }
+    }
+}
+
+/// `None` if the aligned offset doesn't fit in the 32-bit address space.
+fn align_to(offset: u32, align: u32) -> Option<u32> {
+    offset.div_ceil(align).checked_mul(align)
+}
+
+struct Layouts<'a> {
+    structs: HashMap<&'a str, &'a [Field]>,
+    done: HashMap<String, StructLayout>,
+    /// Structs being laid out, to stop at ones that contain themselves.
+    active: HashSet<&'a str>,
+}
+
+impl<'a> Layouts<'a> {
+    fn of_type(&mut self, ty: &'a Type) -> Option<Layout> {
+        match &ty.kind {
+            TypeKind::Name(name) => match NumType::from_name(name) {
+                Some(ty) => Some(ty.layout()),
+                None => self.of_struct(name).map(|s| Layout {
+                    size: s.size,
+                    align: s.align,
+                }),
+            },
+            TypeKind::Pointer(_) => Some(POINTER),
+            TypeKind::Tuple(types) => {
+                let fields = types.iter().enumerate().map(|(i, ty)| (i.to_string(), ty));
+                self.place(fields).map(|s| Layout {
+                    size: s.size,
+                    align: s.align,
+                })
+            }
+        }
+    }
+
+    fn of_struct(&mut self, name: &str) -> Option<&StructLayout> {
+        if !self.done.contains_key(name) {
+            let (&name, &fields) = self.structs.get_key_value(name)?;
+            if !self.active.insert(name) {
+                return None;
+            }
+            let layout = self.place(fields.iter().map(|f| (f.name.clone(), &f.ty)));
+            self.active.remove(name);
+            self.done.insert(name.to_owned(), layout?);
+        }
+        self.done.get(name)
+    }
+
+    fn place(&mut self, fields: impl Iterator<Item = (String, &'a Type)>) -> Option<StructLayout> {
+        let mut offset = 0;
+        let mut align = 1;
+        let mut placed = Vec::new();
+        for (name, ty) in fields {
+            let layout = self.of_type(ty)?;
+            offset = align_to(offset, layout.align)?;
+            placed.push(FieldLayout {
+                name,
+                offset,
+                size: layout.size,
+            });
+            offset = offset.checked_add(layout.size)?;
+            align = align.max(layout.align);
+        }
+        Some(StructLayout {
+            size: align_to(offset, align)?,
+            align,
+            fields: placed,
+        })
+    }
+}
+
+/// Lays out every struct of `program`. Structs with a field of an unknown
+/// type or that contain themselves are left out, name resolution reports
+/// those. So are structs too large for the 32-bit address space.
+pub fn struct_layouts(program: &Program) -> HashMap<String, StructLayout> {
+    let structs: HashMap<&str, &[Field]> = program
+        .iter()
+        .filter_map(|stat| match &stat.kind {
+            StatKind::Struct { name, fields } => Some((name.as_str(), fields.as_slice())),
+            _ => None,
+        })
+        .collect();
+    let mut layouts = Layouts {
+        structs: structs.clone(),
+        done: HashMap::new(),
+        active: HashSet::new(),
+    };
+    for name in structs.keys() {
+        layouts.of_struct(name);
+    }
+    layouts.done
+}
@@ -1,174 +1,691 @@
-use logos::{Lexer, Logos};
-
-fn char_to_radix(c: char) -> Option<u8> {
-    match c {
-        'b' => Some(2),
-        't' => Some(3),
-        'q' => Some(4),
-        'p' => Some(5),
-        'h' => Some(6),
-        's' => Some(7),
-        'o' => Some(8),
-        'e' => Some(9),
-        'd' => Some(10),
-        'l' => Some(11),
-        'z' => Some(12),
-        'x' => Some(16),
-        _ => None,
-    }
-}
-
-fn parse_float(lex: &mut Lexer<Token>) -> Option<f64> {
-    let slice = lex.slice();
-    let f: f64 = slice.parse().ok()?;
-    Some(f)
-}
-
-fn parse_radix_number(lex: &mut Lexer<Token>) -> Option<i64> {
-    let slice = lex.slice();
-    let radix_char = slice.bytes().nth(1)? as char;
-    let radix = char_to_radix(radix_char);
-    assert!(
-        radix.is_some(),
-        "{} is not a valid radix prefix in number {}",
-        radix_char,
-        slice
-    );
-    let n: i64 = i64::from_str_radix(&slice[2..], radix.unwrap() as u32).ok()?;
-    Some(n)
-}
-
-fn parse_number(lex: &mut Lexer<Token>) -> Option<i64> {
-    let slice = lex.slice();
-    let n: i64 = slice.parse().ok()?;
-    Some(n)
-}
-
-// Questionable code..
-fn parse_string(lex: &mut Lexer<Token>) -> Option<String> {
-    let mut remainder = lex.remainder().chars().enumerate().peekable();
-    let mut res = String::new();
-    loop {
-        match remainder.next() {
-            Some((_, '\\')) => {
-                // check the first character
-                let (_, header_char) = remainder
-                    .next()
-                    .expect("expected escape character, got <eof>");
-
-                if let Some(radix) = char_to_radix(header_char) {
-                    //  ^ \x6e, \d71
-                    // if there is no applicable digit after, then treat as normal escape
-                    let (_, header_digit) = remainder
-                        .peek()
-                        .expect("expected integer escape, got <eof>");
-                    if header_digit.is_digit(radix as u32) {
-                        // go on parsing as normal
-                        let mut num = String::from(remainder.next().unwrap().1);
-                        while {
-                            let peek = remainder.peek();
-                            peek.is_some() && peek.unwrap().1.is_digit(radix as u32)
-                        } {
-                            // if the peeked character is a digit, then push it
-                            num.push(remainder.next().unwrap().1);
-                        }
-
-                        let codepoint = u32::from_str_radix(&num, radix as u32);
-                        assert!(codepoint.is_ok(), "max escape value is {}", u32::MAX);
-
-                        let escaped = char::from_u32(codepoint.unwrap());
-                        assert!(
-                            escaped.is_some(),
-                            "invalid codepoint, cannot add \\{}{} as part of escape",
-                            header_char,
-                            num
-                        );
-
-                        // push the codepoint
-                        res.push(escaped.unwrap());
-                    } else {
-                        // treat header character as escaped
-                        res.push(header_char);
-                    }
-                } else {
-                    // is not an escape
-                    res.push(header_char);
-                }
-            }
-            Some((i, '"')) => {
-                lex.bump(i + 1);
-                break;
-            }
-            Some((_, c)) => res.push(c),
-            None => panic!("incomplete string literal"),
-        }
-    }
-
-    Some(res)
-}
-
-#[derive(Logos, Debug, PartialEq)]
-pub enum Token {
-    #[token("data")]
-    Data,
-
-    #[token("\"", parse_string, priority = 3)]
-    String(String),
-
-    #[token("global")]
-    Global,
-
-    #[token("export")]
-    Export,
-
-    #[token("return")]
-    Return,
-
-    #[token("fn")]
-    Fn,
-
-    #[token("=", priority = 3)]
-    Equals,
-
-    #[token("(", priority = 3)]
-    OpenParen,
-
-    #[token(")", priority = 3)]
-    CloseParen,
-
-    #[token("{", priority = 3)]
-    OpenBrace,
-
-    #[token("}", priority = 3)]
-    CloseBrace,
-
-    #[token("[", priority = 3)]
-    OpenBracket,
-
-    #[token("]", priority = 3)]
-    CloseBracket,
-
-    #[token(";", priority = 3)]
-    Semicolon,
-
-    #[token(",", priority = 3)]
-    Comma,
-
-    #[regex(r"\^|\||&|==|!=|>=|<=|>>|<<|>|<|\+|-|\*\*|/|\*", |lex| lex.slice().to_owned(), priority = 4)]
-    Op(String),
-
-    #[regex(r"\-?\d*\.\d+", parse_float, priority = 4)]
-    Float(f64),
-
-    #[regex(r"\-?0[^\d\s]\S+", parse_radix_number, priority = 3)]
-    #[regex(r"\-?\d+", parse_number, priority = 3)]
-    Integer(i64),
-
-    #[error]
-    #[regex(r"\s+", logos::skip, priority = 2)]
-    Error,
-
-    // TODO: figure out catch-all system
-    #[regex(r#"[^\s=(){}\[\];^|&<>+\-*/,"]+"#, |lex| lex.slice().to_owned(), priority = 1)]
-    Ident(String),
-}
+use crate::diagnostics::{codes, Diagnostic};
+use logos::{Filter, Lexer, Logos};
+use serde::Serialize;
+use std::{convert::TryFrom, fmt, iter::Peekable, str::CharIndices};
+use unicode_xid::UnicodeXID;
+
+pub type Span = logos::Span;
+
+/// The numeric types a literal can be suffixed with, e.g. `255u8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NumType {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+}
+
+impl NumType {
+    pub fn from_name(name: &str) -> Option<NumType> {
+        match name {
+            "i8" => Some(NumType::I8),
+            "i16" => Some(NumType::I16),
+            "i32" => Some(NumType::I32),
+            "i64" => Some(NumType::I64),
+            "u8" => Some(NumType::U8),
+            "u16" => Some(NumType::U16),
+            "u32" => Some(NumType::U32),
+            "u64" => Some(NumType::U64),
+            "f32" => Some(NumType::F32),
+            "f64" => Some(NumType::F64),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NumType::I8 => "i8",
+            NumType::I16 => "i16",
+            NumType::I32 => "i32",
+            NumType::I64 => "i64",
+            NumType::U8 => "u8",
+            NumType::U16 => "u16",
+            NumType::U32 => "u32",
+            NumType::U64 => "u64",
+            NumType::F32 => "f32",
+            NumType::F64 => "f64",
+        }
+    }
+
+    pub fn is_float(self) -> bool {
+        matches!(self, NumType::F32 | NumType::F64)
+    }
+
+    /// The smallest and largest value of an integer type.
+    pub fn int_range(self) -> Option<(i128, i128)> {
+        match self {
+            NumType::I8 => Some((i8::MIN as i128, i8::MAX as i128)),
+            NumType::I16 => Some((i16::MIN as i128, i16::MAX as i128)),
+            NumType::I32 => Some((i32::MIN as i128, i32::MAX as i128)),
+            NumType::I64 => Some((i64::MIN as i128, i64::MAX as i128)),
+            NumType::U8 => Some((0, u8::MAX as i128)),
+            NumType::U16 => Some((0, u16::MAX as i128)),
+            NumType::U32 => Some((0, u32::MAX as i128)),
+            NumType::U64 => Some((0, u64::MAX as i128)),
+            NumType::F32 | NumType::F64 => None,
+        }
+    }
+}
+
+impl fmt::Display for NumType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A number literal along with its type suffix, if it has one.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Number<T> {
+    pub value: T,
+    pub ty: Option<NumType>,
+}
+
+impl<T: fmt::Display> fmt::Display for Number<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)?;
+        if let Some(ty) = self.ty {
+            write!(f, "{}", ty)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum LexErrorKind {
+    UnexpectedCharacter,
+    UnterminatedString,
+    UnterminatedComment,
+    InvalidEscape,
+    UnknownRadix(char),
+    InvalidDigit(u32),
+    InvalidSuffix,
+    OutOfRange(NumType),
+    ReservedKeyword,
+    InvalidCharLiteral,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub span: Span,
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            LexErrorKind::UnexpectedCharacter => write!(f, "unexpected character"),
+            LexErrorKind::UnterminatedString => write!(f, "unterminated string literal"),
+            LexErrorKind::UnterminatedComment => write!(f, "unterminated block comment"),
+            LexErrorKind::InvalidEscape => write!(f, "invalid escape in string literal"),
+            LexErrorKind::UnknownRadix(c) => write!(f, "unknown radix prefix `0{}`", c),
+            LexErrorKind::InvalidDigit(radix) => {
+                write!(f, "invalid digit for a base {} literal", radix)
+            }
+            LexErrorKind::InvalidSuffix => write!(f, "invalid suffix for number literal"),
+            LexErrorKind::OutOfRange(ty) => write!(f, "literal out of range for `{}`", ty),
+            LexErrorKind::ReservedKeyword => write!(f, "reserved keyword used as an identifier"),
+            LexErrorKind::InvalidCharLiteral => {
+                write!(f, "character literal must contain exactly one character")
+            }
+        }
+    }
+}
+
+impl From<LexError> for Diagnostic {
+    fn from(e: LexError) -> Diagnostic {
+        let code = match e.kind {
+            LexErrorKind::UnexpectedCharacter => codes::UNEXPECTED_CHARACTER,
+            LexErrorKind::UnterminatedString => codes::UNTERMINATED_STRING,
+            LexErrorKind::UnterminatedComment => codes::UNTERMINATED_COMMENT,
+            LexErrorKind::InvalidEscape => codes::INVALID_ESCAPE,
+            LexErrorKind::UnknownRadix(_) | LexErrorKind::InvalidDigit(_) => codes::INVALID_DIGIT,
+            LexErrorKind::InvalidSuffix => codes::INVALID_SUFFIX,
+            LexErrorKind::OutOfRange(_) => codes::LITERAL_OUT_OF_RANGE,
+            LexErrorKind::ReservedKeyword => codes::RESERVED_KEYWORD,
+            LexErrorKind::InvalidCharLiteral => codes::INVALID_CHAR_LITERAL,
+        };
+
+        let diagnostic = Diagnostic::error(code, e.to_string(), e.span);
+        match e.kind {
+            LexErrorKind::OutOfRange(ty) => match ty.int_range() {
+                Some((min, max)) => {
+                    diagnostic.with_note(format!("`{}` ranges from {} to {}", ty, min, max))
+                }
+                None => diagnostic.with_note(format!("the value is infinite as `{}`", ty)),
+            },
+            LexErrorKind::InvalidSuffix => diagnostic
+                .with_note("valid suffixes are i8, i16, i32, i64, u8, u16, u32, u64, f32 and f64"),
+            _ => diagnostic,
+        }
+    }
+}
+
+/// Iterator over the tokens of a source string, see [`tokenize`].
+pub struct Tokens<'a> {
+    lexer: Lexer<'a, Token>,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Result<(Token, Span), LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self.lexer.next()?;
+        let span = self.lexer.span();
+        Some(match token {
+            Token::Error => Err(LexError {
+                kind: self
+                    .lexer
+                    .extras
+                    .take()
+                    .unwrap_or(LexErrorKind::UnexpectedCharacter),
+                span,
+            }),
+            token => Ok((token, span)),
+        })
+    }
+}
+
+/// Lexes `source` into tokens and their byte spans. Input that doesn't form
+/// a valid token is yielded as a [`LexError`] explaining why.
+pub fn tokenize(source: &str) -> Tokens<'_> {
+    Tokens {
+        lexer: Token::lexer(source),
+    }
+}
+
+fn char_to_radix(c: char) -> Option<u8> {
+    match c {
+        'b' => Some(2),
+        't' => Some(3),
+        'q' => Some(4),
+        'p' => Some(5),
+        'h' => Some(6),
+        's' => Some(7),
+        'o' => Some(8),
+        'e' => Some(9),
+        'd' => Some(10),
+        'l' => Some(11),
+        'z' => Some(12),
+        'x' => Some(16),
+        _ => None,
+    }
+}
+
+/// Records why the current token failed to lex, see [`Tokens`].
+fn fail<T>(lex: &mut Lexer<Token>, kind: LexErrorKind) -> Option<T> {
+    lex.extras = Some(kind);
+    None
+}
+
+/// Splits the digits of a number literal from its type suffix, which starts
+/// at the first character that isn't a digit of `radix` or an `_`.
+fn split_suffix(s: &str, radix: u32) -> (&str, &str) {
+    let end = s
+        .find(|c: char| !(c.is_digit(radix) || c == '_'))
+        .unwrap_or(s.len());
+    s.split_at(end)
+}
+
+/// Checks that an integer literal fits the type of its suffix. Without a
+/// suffix, anything from `i64::MIN` up to `u64::MAX` is accepted.
+fn int_literal(
+    lex: &mut Lexer<Token>,
+    negative: bool,
+    digits: &str,
+    radix: u32,
+    suffix: &str,
+) -> Option<Number<i128>> {
+    if suffix.starts_with(|c: char| c.is_ascii_digit()) {
+        return fail(lex, LexErrorKind::InvalidDigit(radix));
+    }
+
+    let ty = match suffix {
+        "" => None,
+        suffix => match NumType::from_name(suffix) {
+            Some(ty) if !ty.is_float() => Some(ty),
+            _ => return fail(lex, LexErrorKind::InvalidSuffix),
+        },
+    };
+
+    let digits: String = digits.chars().filter(|&c| c != '_').collect();
+    if digits.is_empty() {
+        return fail(lex, LexErrorKind::InvalidDigit(radix));
+    }
+
+    let range_ty = ty.unwrap_or(if negative { NumType::I64 } else { NumType::U64 });
+    let (min, max) = range_ty.int_range()?;
+
+    // the digits are valid at this point, so parsing only fails on overflow
+    let value = u128::from_str_radix(&digits, radix)
+        .ok()
+        .and_then(|n| i128::try_from(n).ok())
+        .map(|n| if negative { -n } else { n });
+
+    match value {
+        Some(value) if min <= value && value <= max => Some(Number { value, ty }),
+        _ => fail(lex, LexErrorKind::OutOfRange(range_ty)),
+    }
+}
+
+fn parse_float(lex: &mut Lexer<Token>) -> Option<Number<f64>> {
+    let slice = lex.slice();
+    let bytes = slice.as_bytes();
+    let mut end = slice
+        .find(|c: char| !(c.is_ascii_digit() || "-_.".contains(c)))
+        .unwrap_or(slice.len());
+
+    // an exponent needs at least one digit, otherwise the `e` starts the suffix
+    if let Some(b'e') | Some(b'E') = bytes.get(end) {
+        let mut exp = end + 1;
+        if let Some(b'+') | Some(b'-') = bytes.get(exp) {
+            exp += 1;
+        }
+        if bytes.get(exp).is_some_and(u8::is_ascii_digit) {
+            end = exp + split_suffix(&slice[exp..], 10).0.len();
+        }
+    }
+
+    let (number, suffix) = slice.split_at(end);
+    let ty = match suffix {
+        "" => None,
+        suffix => match NumType::from_name(suffix) {
+            Some(ty) if ty.is_float() => Some(ty),
+            _ => return fail(lex, LexErrorKind::InvalidSuffix),
+        },
+    };
+
+    let number: String = number.chars().filter(|&c| c != '_').collect();
+    let value: f64 = number.parse().ok()?;
+    let finite = match ty {
+        Some(NumType::F32) => (value as f32).is_finite(),
+        _ => value.is_finite(),
+    };
+
+    if finite {
+        Some(Number { value, ty })
+    } else {
+        fail(lex, LexErrorKind::OutOfRange(ty.unwrap_or(NumType::F64)))
+    }
+}
+
+fn parse_radix_number(lex: &mut Lexer<Token>) -> Option<Number<i128>> {
+    let slice = lex.slice();
+    let (negative, digits) = match slice.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, slice),
+    };
+
+    let radix_char = *digits.as_bytes().get(1)? as char;
+    let radix = match (char_to_radix(radix_char), radix_char) {
+        (Some(radix), _) => radix as u32,
+        // a suffixed zero such as `0u8`
+        (None, 'i') | (None, 'u') => return int_literal(lex, negative, "0", 10, &digits[1..]),
+        (None, _) => return fail(lex, LexErrorKind::UnknownRadix(radix_char)),
+    };
+
+    let (digits, suffix) = split_suffix(&digits[2..], radix);
+    int_literal(lex, negative, digits, radix, suffix)
+}
+
+fn parse_number(lex: &mut Lexer<Token>) -> Option<Number<i128>> {
+    let slice = lex.slice();
+    let (negative, digits) = match slice.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, slice),
+    };
+
+    let (digits, suffix) = split_suffix(digits, 10);
+    int_literal(lex, negative, digits, 10, suffix)
+}
+
+/// Words that aren't keywords yet but are set aside for upcoming syntax.
+pub const RESERVED_KEYWORDS: &[&str] = &[
+    "as", "break", "const", "continue", "else", "enum", "false", "for", "if", "impl", "in", "let",
+    "loop", "match", "mut", "self", "true", "type", "while",
+];
+
+/// Identifiers follow Unicode's XID rules, with `_` also allowed at the
+/// start. The regexes only match ASCII and the first non-ASCII character,
+/// the rest of the identifier is taken here.
+fn ident(lex: &mut Lexer<Token>) -> Option<String> {
+    let mut chars = lex.slice().chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c == '_' || UnicodeXID::is_xid_start(c))
+        && chars.all(UnicodeXID::is_xid_continue);
+    if !valid {
+        return fail(lex, LexErrorKind::UnexpectedCharacter);
+    }
+
+    let rest = lex
+        .remainder()
+        .find(|c: char| !UnicodeXID::is_xid_continue(c))
+        .unwrap_or(lex.remainder().len());
+    lex.bump(rest);
+
+    let slice = lex.slice();
+    if RESERVED_KEYWORDS.contains(&slice) {
+        return fail(lex, LexErrorKind::ReservedKeyword);
+    }
+    Some(slice.to_owned())
+}
+
+/// Skips a `/* */` comment, which may contain nested block comments.
+fn block_comment(lex: &mut Lexer<Token>) -> Filter<()> {
+    let bytes = lex.remainder().as_bytes();
+    let mut depth = 1;
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        match &bytes[i..i + 2] {
+            b"/*" => {
+                depth += 1;
+                i += 2;
+            }
+            b"*/" => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    lex.bump(i);
+                    return Filter::Skip;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+
+    lex.bump(bytes.len());
+    lex.extras = Some(LexErrorKind::UnterminatedComment);
+    Filter::Emit(())
+}
+
+/// A character of a quoted literal, either written out or escaped.
+enum Unit {
+    Char(char),
+    /// A numeric escape such as `\x6e`, whose meaning depends on the literal.
+    Value(u32),
+}
+
+/// Reads an escape sequence, the `\` has already been consumed.
+///
+/// Besides the usual `\n \r \t \0 \\ \" \'` and `\u{...}`, a radix
+/// character followed by digits in that radix is a numeric escape,
+/// e.g. `\x6e` or `\d71`. `\t` is always a tab, so there are no ternary
+/// escapes.
+fn escape(chars: &mut Peekable<CharIndices>) -> Option<Unit> {
+    let (_, header_char) = chars.next()?;
+    let c = match header_char {
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        '0' => '\0',
+        '\\' => '\\',
+        '"' => '"',
+        '\'' => '\'',
+        'u' => {
+            chars.next_if(|&(_, c)| c == '{')?;
+            let num = digits(chars, 16);
+            chars.next_if(|&(_, c)| c == '}')?;
+            if num.is_empty() || num.len() > 6 {
+                return None;
+            }
+            char::from_u32(u32::from_str_radix(&num, 16).ok()?)?
+        }
+        _ => {
+            let radix = char_to_radix(header_char)? as u32;
+            let num = digits(chars, radix);
+            if num.is_empty() {
+                return None;
+            }
+            return u32::from_str_radix(&num, radix).ok().map(Unit::Value);
+        }
+    };
+    Some(Unit::Char(c))
+}
+
+fn digits(chars: &mut Peekable<CharIndices>, radix: u32) -> String {
+    let mut num = String::new();
+    while let Some((_, c)) = chars.next_if(|&(_, c)| c.is_digit(radix)) {
+        num.push(c);
+    }
+    num
+}
+
+/// Walks a quoted literal up to the closing `quote`, handing every character
+/// to `push`, which returns false if it can't be represented. The whole
+/// literal is consumed even if part of it is invalid.
+fn parse_quoted(
+    lex: &mut Lexer<Token>,
+    quote: char,
+    mut push: impl FnMut(Unit) -> bool,
+) -> Result<(), LexErrorKind> {
+    let mut remainder = lex.remainder().char_indices().peekable();
+    let mut valid = true;
+    loop {
+        match remainder.next() {
+            Some((_, '\\')) => {
+                if remainder.peek().is_none() {
+                    break;
+                }
+                valid &= escape(&mut remainder).is_some_and(&mut push);
+            }
+            Some((i, c)) if c == quote => {
+                lex.bump(i + c.len_utf8());
+                return if valid {
+                    Ok(())
+                } else {
+                    Err(LexErrorKind::InvalidEscape)
+                };
+            }
+            Some((_, c)) => valid &= push(Unit::Char(c)),
+            None => break,
+        }
+    }
+
+    // incomplete literal, the rest of the input belongs to it
+    lex.bump(lex.remainder().len());
+    Err(LexErrorKind::UnterminatedString)
+}
+
+/// Numeric escapes in strings are codepoints, so `"\xFF"` is `ÿ`.
+fn parse_string(lex: &mut Lexer<Token>) -> Option<String> {
+    let mut res = String::new();
+    let parsed = parse_quoted(lex, '"', |unit| match unit {
+        Unit::Char(c) => {
+            res.push(c);
+            true
+        }
+        Unit::Value(v) => char::from_u32(v).map(|c| res.push(c)).is_some(),
+    });
+
+    match parsed {
+        Ok(()) => Some(res),
+        Err(kind) => fail(lex, kind),
+    }
+}
+
+/// Numeric escapes in byte strings are single bytes, so `b"\xFF"` is one
+/// byte. Other characters are stored as UTF-8.
+fn parse_byte_string(lex: &mut Lexer<Token>) -> Option<Vec<u8>> {
+    let mut res = Vec::new();
+    let parsed = parse_quoted(lex, '"', |unit| match unit {
+        Unit::Char(c) => {
+            res.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            true
+        }
+        Unit::Value(v) => u8::try_from(v).map(|b| res.push(b)).is_ok(),
+    });
+
+    match parsed {
+        Ok(()) => Some(res),
+        Err(kind) => fail(lex, kind),
+    }
+}
+
+/// Character literals hold a single codepoint, numeric escapes included,
+/// so `'\x41'` is `'A'`.
+fn parse_char(lex: &mut Lexer<Token>) -> Option<char> {
+    let mut res = Vec::new();
+    let parsed = parse_quoted(lex, '\'', |unit| match unit {
+        Unit::Char(c) => {
+            res.push(c);
+            true
+        }
+        Unit::Value(v) => char::from_u32(v).map(|c| res.push(c)).is_some(),
+    });
+
+    match (parsed, res.as_slice()) {
+        (Ok(()), &[c]) => Some(c),
+        (Ok(()), _) => fail(lex, LexErrorKind::InvalidCharLiteral),
+        (Err(kind), _) => fail(lex, kind),
+    }
+}
+
+#[derive(Logos, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[logos(extras = Option<LexErrorKind>)]
+pub enum Token {
+    #[token("data")]
+    Data,
+
+    #[token("\"", parse_string, priority = 3)]
+    String(String),
+
+    #[token("b\"", parse_byte_string, priority = 3)]
+    ByteString(Vec<u8>),
+
+    #[token("'", parse_char, priority = 3)]
+    Char(char),
+
+    #[token("global")]
+    Global,
+
+    #[token("export")]
+    Export,
+
+    #[token("return")]
+    Return,
+
+    #[token("fn")]
+    Fn,
+
+    #[token("use")]
+    Use,
+
+    #[token("struct")]
+    Struct,
+
+    #[token("=", priority = 3)]
+    Equals,
+
+    #[token(":", priority = 3)]
+    Colon,
+
+    #[token(".", priority = 3)]
+    Dot,
+
+    #[token("->", priority = 3)]
+    Arrow,
+
+    #[token("(", priority = 3)]
+    OpenParen,
+
+    #[token(")", priority = 3)]
+    CloseParen,
+
+    #[token("{", priority = 3)]
+    OpenBrace,
+
+    #[token("}", priority = 3)]
+    CloseBrace,
+
+    #[token("[", priority = 3)]
+    OpenBracket,
+
+    #[token("]", priority = 3)]
+    CloseBracket,
+
+    #[token(";", priority = 3)]
+    Semicolon,
+
+    #[token(",", priority = 3)]
+    Comma,
+
+    #[regex(r"\^|\||&|==|!=|>=|<=|>>|<<|>|<|\+|-|\*\*|/|\*", |lex| lex.slice().to_owned(), priority = 4)]
+    Op(String),
+
+    #[regex(
+        r"-?([0-9][0-9_]*)?\.[0-9][0-9_]*([eE][+-]?[0-9][0-9_]*)?([a-zA-Z][0-9a-zA-Z_]*)?",
+        parse_float,
+        priority = 4
+    )]
+    // above radix literals: `0e5` is an exponent like `1e5`, not base 9
+    #[regex(
+        r"-?[0-9][0-9_]*[eE][+-]?[0-9][0-9_]*([a-zA-Z][0-9a-zA-Z_]*)?",
+        parse_float,
+        priority = 7
+    )]
+    #[regex(r"-?[0-9][0-9_]*f(32|64)", parse_float, priority = 7)]
+    Float(Number<f64>),
+
+    #[regex(r"-?0[a-zA-Z][0-9a-zA-Z_]*", parse_radix_number, priority = 6)]
+    #[regex(r"-?[0-9][0-9_]*([a-zA-Z][0-9a-zA-Z_]*)?", parse_number, priority = 3)]
+    Integer(Number<i128>),
+
+    /// A `///` comment, the text after the slashes is kept as is.
+    #[regex(r"///([^/\n][^\n]*)?", |lex| lex.slice()[3..].to_owned(), priority = 3)]
+    DocComment(String),
+
+    #[error]
+    #[regex(r"\s+", logos::skip, priority = 2)]
+    #[regex(r"//[^\n]*", logos::skip, priority = 2)]
+    #[token("/*", block_comment, priority = 3)]
+    Error,
+
+    #[regex(r"[_a-zA-Z][_a-zA-Z0-9]*", ident)]
+    #[regex(r"[_a-zA-Z0-9]*[^\x00-\x7F]", ident)]
+    Ident(String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Data => write!(f, "'data'"),
+            Token::String(s) => write!(f, "string {:?}", s),
+            Token::ByteString(b) => write!(f, "byte string b\"{}\"", b.escape_ascii()),
+            Token::Char(c) => write!(f, "character {:?}", c),
+            Token::Global => write!(f, "'global'"),
+            Token::Export => write!(f, "'export'"),
+            Token::Return => write!(f, "'return'"),
+            Token::Fn => write!(f, "'fn'"),
+            Token::Use => write!(f, "'use'"),
+            Token::Struct => write!(f, "'struct'"),
+            Token::Equals => write!(f, "'='"),
+            Token::Colon => write!(f, "':'"),
+            Token::Dot => write!(f, "'.'"),
+            Token::Arrow => write!(f, "'->'"),
+            Token::OpenParen => write!(f, "'('"),
+            Token::CloseParen => write!(f, "')'"),
+            Token::OpenBrace => write!(f, "'{{'"),
+            Token::CloseBrace => write!(f, "'}}'"),
+            Token::OpenBracket => write!(f, "'['"),
+            Token::CloseBracket => write!(f, "']'"),
+            Token::Semicolon => write!(f, "';'"),
+            Token::Comma => write!(f, "','"),
+            Token::Op(op) => write!(f, "'{}'", op),
+            Token::Float(n) => write!(f, "float {}", n),
+            Token::Integer(n) => write!(f, "integer {}", n),
+            Token::DocComment(_) => write!(f, "doc comment"),
+            Token::Error => write!(f, "invalid token"),
+            Token::Ident(s) => write!(f, "identifier `{}`", s),
+        }
+    }
+}
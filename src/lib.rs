@@ -1,11 +1,104 @@
-mod compiler;
-pub mod lexer;
-mod operators;
-mod parser;
-
-use parser::Parser;
-
-pub fn parse_string(s: impl AsRef<str>) -> parser::Result<parser::Program> {
-    let mut parser = Parser::new(&s);
-    parser.parse()
-}
+//! A compiler for the Eretria language, which maps closely to WebAssembly.
+//!
+//! # Stability
+//!
+//! [`ast`], [`diagnostics`], [`layout`], [`compile`] and the other functions
+//! at the crate root are the supported API and only change in breaking
+//! releases. [`lexer`] and [`visit`] are exposed for tooling such as the
+//! language server and may change in any release.
+//!
+//! New syntax adds variants to the enums of the syntax tree, such as
+//! [`ExprKind`] and [`StatKind`], and new errors add variants to
+//! [`LexErrorKind`](lexer::LexErrorKind). These enums are
+//! `#[non_exhaustive]`, so a `match` on them needs a `_` arm.
+
+pub mod ast;
+mod compiler;
+pub mod diagnostics;
+pub mod layout;
+pub mod lexer;
+mod module;
+mod operators;
+mod parser;
+mod printer;
+mod resolve;
+pub mod visit;
+
+pub use ast::{Expr, ExprKind, Program, Span, Stat, StatKind};
+use compiler::Compiler;
+pub use compiler::Options;
+use diagnostics::Diagnostics;
+pub use module::{check_sources, import_path};
+use parser::Parser;
+use std::io;
+
+pub fn parse_string(s: impl AsRef<str>) -> Result<Program, Diagnostics> {
+    let (program, errors) = parse_partial(s);
+    if errors.is_empty() {
+        Ok(program)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Parses as much of the input as possible, returning every error found
+/// alongside the partial program. Useful for tooling that works on broken code.
+pub fn parse_partial(s: impl AsRef<str>) -> (Program, Diagnostics) {
+    let mut parser = Parser::new(&s);
+    parser.parse()
+}
+
+/// Whether `s` ends inside a `{` or `(` that is still open, or in a doc
+/// comment still waiting for its item, so that more input is needed before
+/// it can parse, e.g. a function body being typed line by line.
+pub fn is_incomplete(s: impl AsRef<str>) -> bool {
+    let mut depth = 0;
+    let mut doc = false;
+    for token in lexer::tokenize(s.as_ref()).flatten() {
+        doc = matches!(token.0, lexer::Token::DocComment(_));
+        match token.0 {
+            lexer::Token::OpenBrace | lexer::Token::OpenParen => depth += 1,
+            lexer::Token::CloseBrace | lexer::Token::CloseParen => depth -= 1,
+            _ => {}
+        }
+    }
+    depth > 0 || doc
+}
+
+/// Runs every check short of code generation: lexing, parsing and name
+/// resolution. There is no file system to follow `use` statements with,
+/// see [`check_sources`] for that.
+pub fn check_string(s: impl AsRef<str>) -> Diagnostics {
+    let source = Source {
+        name: String::new(),
+        text: s.as_ref().to_owned(),
+    };
+    let (_, mut errors) = check_sources(vec![source], |_| Err(io::ErrorKind::NotFound.into()));
+    errors.remove(0)
+}
+
+/// A named piece of source code, e.g. a file given on the command line.
+#[derive(Debug, Clone)]
+pub struct Source {
+    pub name: String,
+    pub text: String,
+}
+
+/// Compiles the input to a WebAssembly module. Fails with every error found
+/// by [`check_string`], or with the reason code generation gave up.
+pub fn compile(s: impl AsRef<str>, options: &Options) -> Result<Vec<u8>, Diagnostics> {
+    let errors = check_string(&s);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let program = parse_string(&s)?;
+    Compiler::new(program, options).compile()
+}
+
+/// Formats the input in canonical style, keeping its comments. Fails with
+/// the parse errors if the input doesn't parse.
+pub fn format_string(s: impl AsRef<str>) -> Result<String, Diagnostics> {
+    let s = s.as_ref();
+    let program = parse_string(s)?;
+    Ok(printer::print(&program, s))
+}
@@ -0,0 +1,835 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use eretria::{
+    ast::{Type, TypeKind},
+    check_sources,
+    diagnostics::Severity,
+    import_path,
+    layout::struct_layouts,
+    lexer::{tokenize, Token},
+    parse_partial,
+    visit::{self, Visitor},
+    Expr, ExprKind, Program, Source, Span, Stat, StatKind,
+};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+        Notification as LspNotification, PublishDiagnostics,
+    },
+    request::{
+        DocumentSymbolRequest, GotoDefinition, HoverRequest, Request as LspRequest,
+        SemanticTokensFullRequest,
+    },
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, DocumentSymbol,
+    DocumentSymbolParams, DocumentSymbolResponse, GotoDefinitionParams, GotoDefinitionResponse,
+    Hover, HoverContents, HoverParams, HoverProviderCapability, Location, MarkupContent,
+    MarkupKind, NumberOrString, OneOf, Position, PublishDiagnosticsParams, Range, SemanticToken,
+    SemanticTokenType, SemanticTokens, SemanticTokensFullOptions, SemanticTokensLegend,
+    SemanticTokensOptions, SemanticTokensParams, SemanticTokensResult,
+    SemanticTokensServerCapabilities, ServerCapabilities, SymbolKind, TextDocumentSyncCapability,
+    TextDocumentSyncKind, Url,
+};
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+const TOKEN_TYPES: &[SemanticTokenType] = &[
+    SemanticTokenType::KEYWORD,
+    SemanticTokenType::FUNCTION,
+    SemanticTokenType::PARAMETER,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::NUMBER,
+    SemanticTokenType::STRING,
+    SemanticTokenType::OPERATOR,
+    SemanticTokenType::COMMENT,
+    SemanticTokenType::TYPE,
+    SemanticTokenType::PROPERTY,
+];
+
+/// Byte offsets of the line starts of a text, to convert spans to the
+/// line and UTF-16 column positions of the protocol.
+struct LineIndex {
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    fn new(text: &str) -> LineIndex {
+        let mut starts = vec![0];
+        starts.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+        LineIndex { starts }
+    }
+
+    fn position(&self, text: &str, offset: usize) -> Position {
+        let offset = offset.min(text.len());
+        let line = self.starts.partition_point(|&start| start <= offset) - 1;
+        let character = text[self.starts[line]..offset].encode_utf16().count();
+        Position::new(line as u32, character as u32)
+    }
+
+    fn offset(&self, text: &str, position: Position) -> usize {
+        let start = match self.starts.get(position.line as usize) {
+            Some(&start) => start,
+            None => return text.len(),
+        };
+        let mut character = 0;
+        for (i, c) in text[start..].char_indices() {
+            if c == '\n' || character >= position.character as usize {
+                return start + i;
+            }
+            character += c.len_utf16();
+        }
+        text.len()
+    }
+
+    fn range(&self, text: &str, span: &Span) -> Range {
+        Range::new(
+            self.position(text, span.start),
+            self.position(text, span.end),
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum NameKind {
+    Function,
+    Type,
+    Parameter,
+    Local,
+    Field,
+}
+
+/// An identifier in a document and what it refers to.
+struct Name {
+    span: Span,
+    text: String,
+    kind: NameKind,
+    /// Where the name is defined in the document. `None` for types,
+    /// functions from other files and names that don't resolve.
+    def: Option<Span>,
+    /// The statement of the function the name occurs in.
+    stat: usize,
+}
+
+/// Collects the names of a function, locals exist from their first
+/// assignment on like in name resolution.
+struct Names<'a> {
+    functions: &'a HashMap<String, (usize, Span)>,
+    structs: &'a HashMap<String, (usize, Span)>,
+    locals: HashMap<String, (NameKind, Span)>,
+    stat: usize,
+    names: Vec<Name>,
+}
+
+impl<'a> Names<'a> {
+    fn push(&mut self, text: &str, span: Span) {
+        let (kind, def) = match self.locals.get(text) {
+            Some((kind, def)) => (*kind, Some(def.clone())),
+            None => (
+                NameKind::Function,
+                self.functions.get(text).map(|(_, def)| def.clone()),
+            ),
+        };
+        self.names.push(Name {
+            span,
+            text: text.to_owned(),
+            kind,
+            def,
+            stat: self.stat,
+        });
+    }
+
+    fn push_type(&mut self, ty: &Type) {
+        match &ty.kind {
+            TypeKind::Name(name) => self.names.push(Name {
+                span: ty.span.clone(),
+                text: name.clone(),
+                kind: NameKind::Type,
+                def: self.structs.get(name).map(|(_, def)| def.clone()),
+                stat: self.stat,
+            }),
+            TypeKind::Tuple(types) => {
+                for ty in types {
+                    self.push_type(ty);
+                }
+            }
+            TypeKind::Pointer(ty) => self.push_type(ty),
+            _ => {}
+        }
+    }
+
+    /// A field name, either where its struct declares it or in a field
+    /// access, whose struct isn't known here.
+    fn push_field(&mut self, text: &str, span: Span, def: Option<Span>) {
+        self.names.push(Name {
+            span,
+            text: text.to_owned(),
+            kind: NameKind::Field,
+            def,
+            stat: self.stat,
+        });
+    }
+
+    fn define(&mut self, text: &str, kind: NameKind, span: Span) {
+        self.locals
+            .entry(text.to_owned())
+            .or_insert((kind, span.clone()));
+        self.push(text, span);
+    }
+}
+
+impl<'a> Visitor for Names<'a> {
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Ident(name) => self.push(name, expr.span.clone()),
+            ExprKind::Assignment(target, value) => {
+                self.visit_expr(value);
+                let targets = match &target.kind {
+                    ExprKind::Tuple(targets) => targets.iter().collect(),
+                    _ => vec![&**target],
+                };
+                for target in targets {
+                    match &target.kind {
+                        ExprKind::Ident(name) => {
+                            self.define(name, NameKind::Local, target.span.clone())
+                        }
+                        _ => self.visit_expr(target),
+                    }
+                }
+            }
+            ExprKind::Field(base, name) => {
+                self.visit_expr(base);
+                let end = expr.span.end;
+                self.push_field(name, end - name.len()..end, None);
+            }
+            _ => visit::walk_expr(self, expr),
+        }
+    }
+}
+
+/// The span of the name of a function or struct statement, which the AST
+/// doesn't keep.
+fn name_span(text: &str, stat: &Stat) -> Option<Span> {
+    let start = stat.span.start;
+    tokenize(&text[stat.span.clone()])
+        .filter_map(|token| token.ok())
+        .skip_while(|(token, _)| !matches!(token, Token::Fn | Token::Struct))
+        .find_map(|(token, span)| match token {
+            Token::Ident(_) => Some(span.start + start..span.end + start),
+            _ => None,
+        })
+}
+
+/// A parsed document and what its identifiers refer to. Broken code is
+/// analysed as far as it parses.
+struct Analysis {
+    text: String,
+    lines: LineIndex,
+    program: Program,
+    /// The statement and the name span of every function, by name.
+    functions: HashMap<String, (usize, Span)>,
+    /// The statement and the name span of every struct, by name.
+    structs: HashMap<String, (usize, Span)>,
+    /// Every identifier in a function or struct, in source order.
+    names: Vec<Name>,
+}
+
+impl Analysis {
+    fn new(text: String) -> Analysis {
+        let (program, _) = parse_partial(&text);
+        let mut functions = HashMap::new();
+        let mut structs = HashMap::new();
+        for (i, stat) in program.iter().enumerate() {
+            let (defined, name) = match &stat.kind {
+                StatKind::Fn { name, .. } => (&mut functions, name),
+                StatKind::Struct { name, .. } => (&mut structs, name),
+                _ => continue,
+            };
+            if let Some(span) = name_span(&text, stat) {
+                defined.entry(name.clone()).or_insert((i, span));
+            }
+        }
+
+        let mut names = Names {
+            functions: &functions,
+            structs: &structs,
+            locals: HashMap::new(),
+            stat: 0,
+            names: Vec::new(),
+        };
+        for (i, stat) in program.iter().enumerate() {
+            names.stat = i;
+            if let StatKind::Struct { name, fields } = &stat.kind {
+                if let Some(span) = name_span(&text, stat) {
+                    names.push_type(&Type {
+                        kind: TypeKind::Name(name.clone()),
+                        span,
+                    });
+                }
+                for field in fields {
+                    let start = field.span.start;
+                    let span = start..start + field.name.len();
+                    names.push_field(&field.name, span.clone(), Some(span));
+                    names.push_type(&field.ty);
+                }
+            }
+            if let StatKind::Fn {
+                name,
+                params,
+                results,
+                body,
+            } = &stat.kind
+            {
+                names.locals.clear();
+                if let Some(span) = name_span(&text, stat) {
+                    names.push(name, span);
+                }
+                for param in params {
+                    let start = param.span.start;
+                    names.define(
+                        &param.name,
+                        NameKind::Parameter,
+                        start..start + param.name.len(),
+                    );
+                    names.push_type(&param.ty);
+                }
+                if let Some(results) = results {
+                    names.push_type(results);
+                }
+                names.visit_expr(body);
+            }
+        }
+        let mut names = names.names;
+        names.sort_by_key(|name| name.span.start);
+
+        Analysis {
+            lines: LineIndex::new(&text),
+            text,
+            program,
+            functions,
+            structs,
+            names,
+        }
+    }
+
+    fn range(&self, span: &Span) -> Range {
+        self.lines.range(&self.text, span)
+    }
+
+    fn offset(&self, position: Position) -> usize {
+        self.lines.offset(&self.text, position)
+    }
+
+    /// The name under the cursor, which may also sit right after it.
+    fn name_at(&self, offset: usize) -> Option<&Name> {
+        self.names
+            .iter()
+            .find(|name| name.span.start <= offset && offset <= name.span.end)
+    }
+
+    /// `fn add(a: i32, b: i32) -> i32` for a function statement, as written
+    /// but on one line.
+    fn header(&self, stat: &Stat) -> String {
+        match &stat.kind {
+            StatKind::Fn { body, .. } => {
+                let header = &self.text[stat.span.start..body.span.start];
+                header.split_whitespace().collect::<Vec<_>>().join(" ")
+            }
+            _ => String::new(),
+        }
+    }
+
+    /// The header of a function followed by its doc comment.
+    fn signature(&self, stat: usize) -> String {
+        let stat = &self.program[stat];
+        let signature = format!("```eretria\n{}\n```", self.header(stat));
+        with_doc(signature, stat)
+    }
+
+    /// The name of a struct, its layout in memory and its doc comment.
+    fn struct_signature(&self, stat: usize) -> String {
+        let name = match &self.program[stat].kind {
+            StatKind::Struct { name, .. } => name,
+            _ => return String::new(),
+        };
+        let mut signature = format!("```eretria\nstruct {}\n```", name);
+        if let Some(layout) = struct_layouts(&self.program).get(name) {
+            signature.push_str(&format!("\nsize {}, align {}", layout.size, layout.align));
+            for field in &layout.fields {
+                signature.push_str(&format!(
+                    "\n- `{}`: offset {}, size {}",
+                    field.name, field.offset, field.size
+                ));
+            }
+        }
+        with_doc(signature, &self.program[stat])
+    }
+}
+
+/// Appends the doc comment of `stat` to a hover text.
+fn with_doc(mut signature: String, stat: &Stat) -> String {
+    if !stat.doc.is_empty() {
+        let doc: Vec<&str> = stat.doc.iter().map(|line| line.trim()).collect();
+        signature.push_str("\n\n");
+        signature.push_str(&doc.join("\n"));
+    }
+    signature
+}
+
+fn markdown(value: String) -> Hover {
+    Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }),
+        range: None,
+    }
+}
+
+/// Answers `req` with `f` if the parameters deserialize.
+fn respond<R: LspRequest>(req: Request, f: impl FnOnce(R::Params) -> R::Result) -> Response {
+    let id = req.id.clone();
+    match req.extract(R::METHOD) {
+        Ok((id, params)) => Response::new_ok(id, f(params)),
+        Err(e) => Response::new_err(id, ErrorCode::InvalidParams as i32, e.to_string()),
+    }
+}
+
+/// The params of a notification. Malformed ones are logged to stderr and
+/// dropped, there is no response to report them in and one bad message
+/// shouldn't stop the server.
+fn params<N: LspNotification>(not: Notification) -> Option<N::Params> {
+    match not.extract(N::METHOD) {
+        Ok(params) => Some(params),
+        Err(e) => {
+            eprintln!("eretria-lsp: ignoring `{}`: {}", N::METHOD, e);
+            None
+        }
+    }
+}
+
+struct Server {
+    connection: Connection,
+    /// Text of the open documents, newer than what is on disk.
+    documents: HashMap<Url, String>,
+}
+
+impl Server {
+    fn run(&mut self) -> Result<()> {
+        while let Ok(message) = self.connection.receiver.recv() {
+            match message {
+                Message::Request(req) => {
+                    if self.connection.handle_shutdown(&req)? {
+                        return Ok(());
+                    }
+                    let response = self.request(req);
+                    self.connection.sender.send(response.into())?;
+                }
+                Message::Notification(not) => self.notification(not)?,
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn request(&self, req: Request) -> Response {
+        match req.method.as_str() {
+            HoverRequest::METHOD => respond::<HoverRequest>(req, |params| self.hover(params)),
+            GotoDefinition::METHOD => {
+                respond::<GotoDefinition>(req, |params| self.definition(params))
+            }
+            DocumentSymbolRequest::METHOD => {
+                respond::<DocumentSymbolRequest>(req, |params| self.document_symbols(params))
+            }
+            SemanticTokensFullRequest::METHOD => {
+                respond::<SemanticTokensFullRequest>(req, |params| self.semantic_tokens(params))
+            }
+            _ => Response::new_err(
+                req.id,
+                ErrorCode::MethodNotFound as i32,
+                format!("unknown request `{}`", req.method),
+            ),
+        }
+    }
+
+    fn notification(&mut self, not: Notification) -> Result<()> {
+        match not.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params = match params::<DidOpenTextDocument>(not) {
+                    Some(params) => params,
+                    None => return Ok(()),
+                };
+                let uri = params.text_document.uri;
+                self.documents
+                    .insert(uri.clone(), params.text_document.text);
+                self.publish_diagnostics(uri)
+            }
+            DidChangeTextDocument::METHOD => {
+                let params = match params::<DidChangeTextDocument>(not) {
+                    Some(params) => params,
+                    None => return Ok(()),
+                };
+                // changes are full documents, the last one wins
+                let uri = params.text_document.uri;
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents.insert(uri.clone(), change.text);
+                }
+                self.publish_diagnostics(uri)
+            }
+            DidCloseTextDocument::METHOD => {
+                let params = match params::<DidCloseTextDocument>(not) {
+                    Some(params) => params,
+                    None => return Ok(()),
+                };
+                let uri = params.text_document.uri;
+                self.documents.remove(&uri);
+                self.send_diagnostics(uri, Vec::new())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Reads a file `use`d by a document, preferring an open document.
+    fn read(&self, path: &Path) -> io::Result<String> {
+        match Url::from_file_path(path)
+            .ok()
+            .and_then(|uri| self.documents.get(&uri))
+        {
+            Some(text) => Ok(text.clone()),
+            None => fs::read_to_string(path),
+        }
+    }
+
+    fn analysis(&self, uri: &Url) -> Option<Analysis> {
+        self.documents
+            .get(uri)
+            .map(|text| Analysis::new(text.clone()))
+    }
+
+    /// Finds the function `name` visible in a document, defined there or
+    /// in a file it `use`s. Returns the file, its analysis and the
+    /// function's statement.
+    fn find_function(
+        &self,
+        uri: &Url,
+        analysis: Analysis,
+        name: &str,
+    ) -> Option<(Url, Analysis, usize)> {
+        if let Some(&(stat, _)) = analysis.functions.get(name) {
+            return Some((uri.clone(), analysis, stat));
+        }
+
+        let path = uri.to_file_path().ok()?;
+        let from = path.to_string_lossy();
+        for stat in &analysis.program {
+            if let StatKind::Use { path: used, names } = &stat.kind {
+                if matches!(names, Some(names) if !names.iter().any(|n| n.name == name)) {
+                    continue;
+                }
+                let used = PathBuf::from(import_path(&from, used));
+                let text = match self.read(&used) {
+                    Ok(text) => text,
+                    Err(_) => continue,
+                };
+                let imported = Analysis::new(text);
+                if let Some(&(stat, _)) = imported.functions.get(name) {
+                    return Some((Url::from_file_path(&used).ok()?, imported, stat));
+                }
+            }
+        }
+        None
+    }
+
+    fn publish_diagnostics(&self, uri: Url) -> Result<()> {
+        let text = match self.documents.get(&uri) {
+            Some(text) => text.clone(),
+            None => return Ok(()),
+        };
+        let name = match uri.to_file_path() {
+            Ok(path) => path.to_string_lossy().into_owned(),
+            Err(_) => uri.to_string(),
+        };
+        let (_, mut errors) = check_sources(vec![Source { name, text }], |path| {
+            self.read(Path::new(path))
+        });
+
+        let text = &self.documents[&uri];
+        let lines = LineIndex::new(text);
+        let diagnostics = errors
+            .remove(0)
+            .into_iter()
+            .map(|d| {
+                let mut message = d.message;
+                for note in &d.notes {
+                    message.push_str("\nnote: ");
+                    message.push_str(note);
+                }
+                let related = d
+                    .labels
+                    .iter()
+                    .map(|label| DiagnosticRelatedInformation {
+                        location: Location::new(uri.clone(), lines.range(text, &label.span)),
+                        message: label.message.clone(),
+                    })
+                    .collect::<Vec<_>>();
+                Diagnostic {
+                    range: lines.range(text, &d.span),
+                    severity: Some(match d.severity {
+                        Severity::Error => DiagnosticSeverity::ERROR,
+                        Severity::Warning => DiagnosticSeverity::WARNING,
+                        Severity::Note => DiagnosticSeverity::INFORMATION,
+                    }),
+                    code: Some(NumberOrString::String(d.code.to_owned())),
+                    source: Some("eretria".to_owned()),
+                    message,
+                    related_information: Some(related).filter(|r| !r.is_empty()),
+                    ..Diagnostic::default()
+                }
+            })
+            .collect();
+        self.send_diagnostics(uri, diagnostics)
+    }
+
+    fn send_diagnostics(&self, uri: Url, diagnostics: Vec<Diagnostic>) -> Result<()> {
+        let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+        let not = Notification::new(PublishDiagnostics::METHOD.to_owned(), params);
+        self.connection.sender.send(not.into())?;
+        Ok(())
+    }
+
+    /// Shows the signature of functions, where locals come from and the
+    /// type of literals.
+    fn hover(&self, params: HoverParams) -> Option<Hover> {
+        let uri = &params.text_document_position_params.text_document.uri;
+        let analysis = self.analysis(uri)?;
+        let offset = analysis.offset(params.text_document_position_params.position);
+
+        if let Some(name) = analysis.name_at(offset) {
+            let function = match &analysis.program[name.stat].kind {
+                StatKind::Fn { name: function, .. } => function.clone(),
+                _ => String::new(),
+            };
+            let value = match name.kind {
+                NameKind::Function => {
+                    let text = name.text.clone();
+                    let (_, analysis, stat) = self.find_function(uri, analysis, &text)?;
+                    analysis.signature(stat)
+                }
+                NameKind::Type => match analysis.structs.get(&name.text) {
+                    Some(&(stat, _)) => analysis.struct_signature(stat),
+                    None => format!("```eretria\n{}\n```", name.text),
+                },
+                NameKind::Parameter => format!(
+                    "```eretria\n{}\n```\nparameter of `{}`",
+                    name.text, function
+                ),
+                NameKind::Local => {
+                    format!("```eretria\n{}\n```\nlocal of `{}`", name.text, function)
+                }
+                NameKind::Field => match &analysis.program[name.stat].kind {
+                    StatKind::Struct { name: owner, .. } => {
+                        format!("```eretria\n{}\n```\nfield of `{}`", name.text, owner)
+                    }
+                    _ => format!("```eretria\n{}\n```\nfield", name.text),
+                },
+            };
+            return Some(markdown(value));
+        }
+
+        let (token, span) = tokenize(&analysis.text)
+            .filter_map(|token| token.ok())
+            .find(|(_, span)| span.start <= offset && offset < span.end)?;
+        let mut value = match token {
+            Token::Integer(number) => number
+                .ty
+                .map_or("{integer}".to_owned(), |ty| ty.to_string()),
+            Token::Float(number) => number.ty.map_or("{float}".to_owned(), |ty| ty.to_string()),
+            Token::Char(_) => "{integer}".to_owned(),
+            _ => return None,
+        };
+        value = format!("```eretria\n{}\n```", value);
+        if let Token::Char(c) = token {
+            value.push_str(&format!("\ncodepoint U+{:04X}", c as u32));
+        }
+        let mut hover = markdown(value);
+        hover.range = Some(analysis.range(&span));
+        Some(hover)
+    }
+
+    fn definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let uri = &params.text_document_position_params.text_document.uri;
+        let analysis = self.analysis(uri)?;
+        let offset = analysis.offset(params.text_document_position_params.position);
+        let name = analysis.name_at(offset)?;
+
+        let location = match &name.def {
+            Some(def) => Location::new(uri.clone(), analysis.range(def)),
+            None if name.kind == NameKind::Function => {
+                let text = name.text.clone();
+                let (uri, analysis, _) = self.find_function(uri, analysis, &text)?;
+                let (_, def) = &analysis.functions[&text];
+                Location::new(uri, analysis.range(def))
+            }
+            None => return None,
+        };
+        Some(GotoDefinitionResponse::Scalar(location))
+    }
+
+    fn document_symbols(&self, params: DocumentSymbolParams) -> Option<DocumentSymbolResponse> {
+        let analysis = self.analysis(&params.text_document.uri)?;
+        let mut symbols = Vec::new();
+        for stat in &analysis.program {
+            let (name, detail, kind, selection) = match &stat.kind {
+                StatKind::Fn { name, .. } => (
+                    name.clone(),
+                    analysis.header(stat),
+                    SymbolKind::FUNCTION,
+                    name_span(&analysis.text, stat).unwrap_or_else(|| stat.span.clone()),
+                ),
+                StatKind::Data { offset, bytes } => (
+                    format!("data[{}]", offset),
+                    format!("{} bytes", bytes.len()),
+                    SymbolKind::CONSTANT,
+                    stat.span.clone(),
+                ),
+                StatKind::Struct { name, .. } => (
+                    name.clone(),
+                    format!("struct {}", name),
+                    SymbolKind::STRUCT,
+                    name_span(&analysis.text, stat).unwrap_or_else(|| stat.span.clone()),
+                ),
+                _ => continue,
+            };
+            let children = match &stat.kind {
+                StatKind::Struct { fields, .. } => Some(
+                    fields
+                        .iter()
+                        .map(|field| {
+                            let start = field.span.start;
+                            #[allow(deprecated)]
+                            DocumentSymbol {
+                                name: field.name.clone(),
+                                detail: Some(field.ty.to_string()),
+                                kind: SymbolKind::FIELD,
+                                tags: None,
+                                deprecated: None,
+                                range: analysis.range(&field.span),
+                                selection_range: analysis.range(&(start..start + field.name.len())),
+                                children: None,
+                            }
+                        })
+                        .collect(),
+                ),
+                _ => None,
+            };
+            #[allow(deprecated)]
+            symbols.push(DocumentSymbol {
+                name,
+                detail: Some(detail),
+                kind,
+                tags: None,
+                deprecated: None,
+                range: analysis.range(&stat.span),
+                selection_range: analysis.range(&selection),
+                children,
+            });
+        }
+        Some(DocumentSymbolResponse::Nested(symbols))
+    }
+
+    /// Classifies the tokens of the lexer, identifiers by what they refer to.
+    fn semantic_tokens(&self, params: SemanticTokensParams) -> Option<SemanticTokensResult> {
+        let analysis = self.analysis(&params.text_document.uri)?;
+        let kinds: HashMap<usize, NameKind> = analysis
+            .names
+            .iter()
+            .map(|name| (name.span.start, name.kind))
+            .collect();
+
+        let mut data = Vec::new();
+        let mut prev = Position::new(0, 0);
+        for (token, span) in tokenize(&analysis.text).filter_map(|token| token.ok()) {
+            let ty = match token {
+                Token::Data
+                | Token::Global
+                | Token::Export
+                | Token::Return
+                | Token::Fn
+                | Token::Struct
+                | Token::Use => SemanticTokenType::KEYWORD,
+                Token::Ident(_) => match kinds.get(&span.start) {
+                    Some(NameKind::Type) => SemanticTokenType::TYPE,
+                    Some(NameKind::Parameter) => SemanticTokenType::PARAMETER,
+                    Some(NameKind::Local) => SemanticTokenType::VARIABLE,
+                    Some(NameKind::Field) => SemanticTokenType::PROPERTY,
+                    _ => SemanticTokenType::FUNCTION,
+                },
+                Token::Integer(_) | Token::Float(_) => SemanticTokenType::NUMBER,
+                Token::String(_) | Token::ByteString(_) | Token::Char(_) => {
+                    SemanticTokenType::STRING
+                }
+                Token::Op(_) | Token::Equals => SemanticTokenType::OPERATOR,
+                Token::DocComment(_) => SemanticTokenType::COMMENT,
+                _ => continue,
+            };
+
+            // a token spanning lines is only highlighted on its first
+            let text = &analysis.text[span.clone()];
+            let text = text.split('\n').next().unwrap_or(text);
+            let start = analysis.lines.position(&analysis.text, span.start);
+            let delta_line = start.line - prev.line;
+            data.push(SemanticToken {
+                delta_line,
+                delta_start: if delta_line == 0 {
+                    start.character - prev.character
+                } else {
+                    start.character
+                },
+                length: text.encode_utf16().count() as u32,
+                token_type: TOKEN_TYPES.iter().position(|t| *t == ty).unwrap_or(0) as u32,
+                token_modifiers_bitset: 0,
+            });
+            prev = start;
+        }
+
+        Some(SemanticTokensResult::Tokens(SemanticTokens {
+            result_id: None,
+            data,
+        }))
+    }
+}
+
+fn main() -> Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(
+            SemanticTokensOptions {
+                legend: SemanticTokensLegend {
+                    token_types: TOKEN_TYPES.to_vec(),
+                    token_modifiers: Vec::new(),
+                },
+                full: Some(SemanticTokensFullOptions::Bool(true)),
+                ..SemanticTokensOptions::default()
+            },
+        )),
+        ..ServerCapabilities::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+
+    let mut server = Server {
+        connection,
+        documents: HashMap::new(),
+    };
+    server.run()?;
+    drop(server);
+    io_threads.join()?;
+    Ok(())
+}
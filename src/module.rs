@@ -0,0 +1,293 @@
+use crate::{
+    diagnostics::{codes, Diagnostic},
+    lexer::Span,
+    parse_partial,
+    parser::{Field, ImportName, Program, StatKind},
+    resolve::{self, Scope},
+    Source,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, io, iter,
+    path::{Component, Path, PathBuf},
+};
+
+/// A `use` statement and the file it loaded.
+struct Import {
+    file: usize,
+    span: Span,
+    names: Option<Vec<ImportName>>,
+}
+
+/// Joins a `use` path onto the directory of the file containing it. `.` and
+/// `..` are folded away so that each file is known by a single name, a
+/// leading `..` is kept. This is how [`check_sources`] finds used files.
+pub fn import_path(from: &str, path: &str) -> String {
+    let joined = Path::new(from)
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(path);
+    let mut normal = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normal.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normal.pop();
+                }
+                _ => normal.push(".."),
+            },
+            c => normal.push(c),
+        }
+    }
+    normal.to_string_lossy().into_owned()
+}
+
+/// The two kinds of names a file defines, which don't clash with each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Kind {
+    Function,
+    Struct,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Kind::Function => write!(f, "function"),
+            Kind::Struct => write!(f, "struct"),
+        }
+    }
+}
+
+/// The functions and structs of a program, with the fields of the structs.
+fn items(program: &Program) -> Vec<(Kind, &str, &[Field])> {
+    program
+        .iter()
+        .filter_map(|stat| match &stat.kind {
+            StatKind::Fn { name, .. } => Some((Kind::Function, name.as_str(), &[][..])),
+            StatKind::Struct { name, fields } => {
+                Some((Kind::Struct, name.as_str(), fields.as_slice()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Checks `sources` as one module, like the files given on the command
+/// line, along with every file they pull in with `use`. Those are read
+/// through `read`, relative to the file using them, and appended to the
+/// returned sources. The diagnostics of each source are returned in order.
+///
+/// The given sources share their functions with each other, every other
+/// file only sees its own functions and structs and the ones it imports.
+/// Importing a name that is already defined, or imported from another
+/// file, is an error.
+pub fn check_sources(
+    mut sources: Vec<Source>,
+    mut read: impl FnMut(&str) -> io::Result<String>,
+) -> (Vec<Source>, Vec<Vec<Diagnostic>>) {
+    let roots = sources.len();
+    let mut index: HashMap<String, usize> = sources
+        .iter()
+        .enumerate()
+        .map(|(i, source)| (import_path("", &source.name), i))
+        .collect();
+    let mut programs = Vec::new();
+    let mut imports = Vec::new();
+    let mut errors = Vec::new();
+
+    // sources grows as `use` statements are found
+    let mut file = 0;
+    while file < sources.len() {
+        let (program, mut file_errors) = parse_partial(&sources[file].text);
+        let mut file_imports = Vec::new();
+        for stat in &program {
+            if let StatKind::Use { path, names } = &stat.kind {
+                let key = import_path(&sources[file].name, path);
+                let target = match index.get(&key) {
+                    Some(&target) => target,
+                    None => match read(&key) {
+                        Ok(text) => {
+                            sources.push(Source {
+                                name: key.clone(),
+                                text,
+                            });
+                            index.insert(key, sources.len() - 1);
+                            sources.len() - 1
+                        }
+                        Err(e) => {
+                            file_errors.push(Diagnostic::error(
+                                codes::IMPORT_FAILED,
+                                format!("cannot read `{}`: {}", key, e),
+                                stat.span.clone(),
+                            ));
+                            continue;
+                        }
+                    },
+                };
+                file_imports.push(Import {
+                    file: target,
+                    span: stat.span.clone(),
+                    names: names.clone(),
+                });
+            }
+        }
+        programs.push(program);
+        imports.push(file_imports);
+        errors.push(file_errors);
+        file += 1;
+    }
+
+    report_cycles(&sources, &imports, &mut errors);
+
+    // names are only resolved once every file is there and parses
+    if errors.iter().all(Vec::is_empty) {
+        let namespace = |file| if file < roots { 0 } else { file };
+        let mut local: HashMap<usize, HashSet<(Kind, &str)>> = HashMap::new();
+        for (file, program) in programs.iter().enumerate() {
+            local.entry(namespace(file)).or_default().extend(
+                items(program)
+                    .into_iter()
+                    .map(|(kind, name, _)| (kind, name)),
+            );
+        }
+
+        let mut scopes = Vec::new();
+        for (file, file_imports) in imports.iter().enumerate() {
+            let mut functions = Vec::new();
+            let mut structs = HashMap::new();
+            // the file each imported name comes from
+            let mut origins: HashMap<(Kind, &str), usize> = HashMap::new();
+            for import in file_imports {
+                let defined = items(&programs[import.file]);
+                let mut found = Vec::new();
+                match &import.names {
+                    Some(wanted) => {
+                        for wanted in wanted {
+                            let before = found.len();
+                            found.extend(
+                                defined
+                                    .iter()
+                                    .filter(|(_, name, _)| *name == wanted.name)
+                                    .map(|&item| (item, wanted.span.clone())),
+                            );
+                            if found.len() == before {
+                                errors[file].push(Diagnostic::error(
+                                    codes::UNRESOLVED_IMPORT,
+                                    format!(
+                                        "no function or struct `{}` in `{}`",
+                                        wanted.name, sources[import.file].name
+                                    ),
+                                    wanted.span.clone(),
+                                ));
+                            }
+                        }
+                    }
+                    None => {
+                        found = defined
+                            .into_iter()
+                            .map(|item| (item, import.span.clone()))
+                            .collect()
+                    }
+                }
+
+                for ((kind, name, fields), span) in found {
+                    let imported = &sources[import.file].name;
+                    let message = if local[&namespace(file)].contains(&(kind, name)) {
+                        format!(
+                            "{} `{}` is imported from `{}` but also defined here",
+                            kind, name, imported
+                        )
+                    } else {
+                        let origin = *origins.entry((kind, name)).or_insert(import.file);
+                        if origin == import.file {
+                            match kind {
+                                Kind::Function => functions.push(name),
+                                Kind::Struct => {
+                                    structs.insert(name, (import.file, fields));
+                                }
+                            }
+                            continue;
+                        }
+                        format!(
+                            "{} `{}` is imported from both `{}` and `{}`",
+                            kind, name, sources[origin].name, imported
+                        )
+                    };
+                    errors[file].push(Diagnostic::error(
+                        codes::DUPLICATE_DEFINITION,
+                        message,
+                        span,
+                    ));
+                }
+            }
+            scopes.push(Scope {
+                name: &sources[file].name,
+                program: &programs[file],
+                namespace: namespace(file),
+                imports: functions,
+                struct_imports: structs,
+            });
+        }
+
+        for (file, resolved) in resolve::resolve(&scopes).into_iter().enumerate() {
+            errors[file].extend(resolved);
+        }
+    }
+
+    for file_errors in &mut errors {
+        file_errors.sort_by_key(|e| e.span.start);
+    }
+    (sources, errors)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    Unvisited,
+    Active,
+    Done,
+}
+
+/// Reports every `use` that closes a cycle, e.g. `a.er` using `b.er`
+/// which uses `a.er` again.
+fn report_cycles(sources: &[Source], imports: &[Vec<Import>], errors: &mut [Vec<Diagnostic>]) {
+    fn visit(
+        file: usize,
+        sources: &[Source],
+        imports: &[Vec<Import>],
+        errors: &mut [Vec<Diagnostic>],
+        state: &mut [Visit],
+        stack: &mut Vec<usize>,
+    ) {
+        state[file] = Visit::Active;
+        stack.push(file);
+        for import in &imports[file] {
+            match state[import.file] {
+                Visit::Unvisited => visit(import.file, sources, imports, errors, state, stack),
+                Visit::Active => {
+                    let start = stack.iter().position(|&f| f == import.file).unwrap_or(0);
+                    let cycle: Vec<&str> = stack[start..]
+                        .iter()
+                        .chain(iter::once(&import.file))
+                        .map(|&f| sources[f].name.as_str())
+                        .collect();
+                    errors[file].push(Diagnostic::error(
+                        codes::IMPORT_CYCLE,
+                        format!("`use` cycle: {}", cycle.join(" -> ")),
+                        import.span.clone(),
+                    ));
+                }
+                Visit::Done => {}
+            }
+        }
+        stack.pop();
+        state[file] = Visit::Done;
+    }
+
+    let mut state = vec![Visit::Unvisited; sources.len()];
+    for file in 0..sources.len() {
+        if state[file] == Visit::Unvisited {
+            visit(file, sources, imports, errors, &mut state, &mut Vec::new());
+        }
+    }
+}
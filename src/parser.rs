@@ -1,310 +1,849 @@
-// TODO: Semantic validation
-
-use crate::{lexer::Token, operators};
-use line_col::LineColLookup;
-use logos::{Lexer, Logos};
-use std::fmt;
-
-#[derive(Debug, Clone)]
-pub struct ParseError(String);
-
-impl ParseError {
-    pub fn format_line<T>(pos: (usize, usize), msg: T) -> ParseError
-    where
-        T: AsRef<str>,
-    {
-        ParseError(format!("{}:{}: {}", pos.0, pos.1, msg.as_ref()))
-    }
-
-    pub fn new<E, G>(pos: (usize, usize), e: E, g: G) -> ParseError
-    where
-        E: fmt::Debug,
-        G: fmt::Debug,
-    {
-        ParseError(format!(
-            "{}:{}: expected {:?}, got {:?}",
-            pos.0, pos.1, e, g
-        ))
-    }
-}
-
-macro_rules! error {
-    ($pos: expr, $e: expr, $g: expr) => {
-        Err(ParseError::new($pos, $e, $g))
-    };
-    ($pos: expr, $msg: expr) => {
-        Err(ParseError::format_line($pos, $msg))
-    };
-}
-
-impl fmt::Display for ParseError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0)
-    }
-}
-
-pub type Result<T> = std::result::Result<T, ParseError>;
-
-#[derive(Debug)]
-pub enum Expr {
-    Paren(Box<Expr>),
-    Block(Vec<Expr>),
-    Assignment(String, Box<Expr>),
-    BinOp(Box<Expr>, String, Box<Expr>),
-    Call(Box<Expr>, Vec<Expr>),
-    Ident(String),
-    Integer(i64),
-    Float(f64),
-    Return(Box<Expr>),
-}
-
-#[derive(Debug)]
-pub enum Stat {
-    Fn(String, Vec<Expr>, Expr),
-    Data(u64, String),
-}
-
-pub type Program = Vec<Stat>;
-
-pub struct Parser<'a> {
-    lexer: Lexer<'a, Token>,
-    peeked: Option<Option<Token>>,
-    linecol: LineColLookup<'a>,
-}
-
-macro_rules! expect {
-    ($compare: expr, $name: expr, $token_type: expr) => {
-        if let Some(token) = $compare {
-            if token == $token_type {
-                token
-            } else {
-                panic!("expected {}, got {:?}", $name, token)
-            }
-        } else {
-            panic!("expected {}, got <eof>", $name)
-        }
-    };
-}
-
-impl<'a> Parser<'a> {
-    pub fn new(source: &'a dyn AsRef<str>) -> Parser<'a> {
-        Parser {
-            lexer: Token::lexer(source.as_ref()),
-            linecol: LineColLookup::new(source.as_ref()),
-            peeked: None,
-        }
-    }
-
-    fn pos(&self) -> (usize, usize) {
-        self.linecol.get(self.lexer.span().end)
-    }
-
-    #[inline]
-    fn peek(&mut self) -> Option<&Token> {
-        let iter = &mut self.lexer;
-        self.peeked.get_or_insert_with(|| iter.next()).as_ref()
-    }
-
-    #[inline]
-    fn next(&mut self) -> Option<Token> {
-        match self.peeked.take() {
-            Some(v) => v,
-            None => self.lexer.next(),
-        }
-    }
-
-    #[inline]
-    fn skip(&mut self) {
-        self.next().unwrap();
-    }
-
-    fn prefixexpr(&mut self) -> Result<Expr> {
-        // we can unwrap here since `None` was checked in `primaryexpr`
-        match self.next().unwrap() {
-            Token::OpenParen => {
-                let expr = Expr::Paren(Box::new(self.expr()?));
-                expect!(self.next(), "')'", Token::CloseParen);
-                Ok(expr)
-            }
-            Token::Float(f) => Ok(Expr::Float(f)),
-            Token::Integer(i) => Ok(Expr::Integer(i)),
-            t => error!(self.pos(), "parentheses, integer, or float", t),
-        }
-    }
-
-    fn primaryexpr(&mut self) -> Result<Expr> {
-        let mut base = match self.peek() {
-            Some(Token::Ident(s)) => {
-                // TODO: possibility to remove to_owned here
-                let owned = s.to_owned();
-                self.skip();
-
-                match self.peek() {
-                    Some(&Token::Equals) => {
-                        self.skip();
-                        Expr::Assignment(owned, Box::new(self.expr()?))
-                    }
-                    _ => Expr::Ident(owned),
-                }
-            }
-            Some(&Token::Return) => {
-                self.skip();
-                Expr::Return(Box::new(self.expr()?))
-            }
-            Some(&Token::OpenBrace) => {
-                self.skip();
-                Expr::Block(self.block()?)
-            }
-            Some(..) => self.prefixexpr()?,
-            None => return error!(self.pos(), "expression", "<eof>"),
-        };
-
-        // arglist
-        while let Some(Token::OpenParen) = self.peek() {
-            self.skip();
-            let mut arglist = Vec::new();
-
-            loop {
-                match self.peek() {
-                    Some(&Token::CloseParen) => {
-                        self.skip();
-                        break;
-                    }
-                    Some(..) => {
-                        arglist.push(self.expr()?);
-
-                        match self.peek() {
-                            Some(&Token::CloseParen) => {
-                                self.skip();
-                                break;
-                            }
-                            Some(&Token::Comma) => {
-                                self.skip();
-                                continue;
-                            }
-                            Some(..) => {
-                                return error!(self.pos(), "')' or ','", self.next().unwrap())
-                            }
-                            None => return error!(self.pos(), "')' or ','", "<eof>"),
-                        }
-                    }
-                    None => {
-                        return error!(
-                            self.pos(),
-                            "incomplete argument list, closing parenthesis not found"
-                        )
-                    }
-                }
-            }
-
-            base = Expr::Call(Box::new(base), arglist)
-        }
-
-        Ok(base)
-    }
-
-    fn subexpr(&mut self, mut lhs: Expr, min_prec: u8) -> Result<Expr> {
-        let mut peek = self.peek();
-        loop {
-            if let Some(Token::Op(op)) = peek {
-                let op_prec = operators::precedence(op)
-                    .unwrap_or_else(|| panic!("foreign operator {} found", op));
-
-                if op_prec >= min_prec {
-                    // TODO: possibility to remove to_owned here
-                    let owned_op = op.to_owned();
-                    self.skip();
-                    let mut rhs = self.primaryexpr()?;
-
-                    peek = self.peek();
-                    loop {
-                        if let Some(Token::Op(next_op)) = peek {
-                            let next_op_prec = operators::precedence(next_op)
-                                .unwrap_or_else(|| panic!("foreign operator {} found", next_op));
-
-                            if next_op_prec > op_prec {
-                                rhs = self.subexpr(rhs, min_prec + 1)?;
-                                peek = self.peek();
-                                continue;
-                            }
-                        }
-                        break;
-                    }
-
-                    lhs = Expr::BinOp(Box::new(lhs), owned_op, Box::new(rhs));
-                    continue;
-                }
-            }
-            break;
-        }
-        Ok(lhs)
-    }
-
-    fn expr(&mut self) -> Result<Expr> {
-        let lhs = self.primaryexpr()?;
-        self.subexpr(lhs, 0)
-    }
-
-    fn block(&mut self) -> Result<Vec<Expr>> {
-        let mut block = Vec::new();
-        while self.peek().is_some() {
-            block.push(self.expr()?);
-            match self.peek() {
-                Some(&Token::Semicolon) => {
-                    self.skip();
-                }
-                _ => break,
-            }
-        }
-
-        // expect close brace
-        match self.next() {
-            Some(Token::CloseBrace) => Ok(block),
-
-            // TODO: add back semicolon error message
-            Some(t) => error!(self.pos(), "'}'", t),
-            None => error!(self.pos(), "'}'", "<eof>"),
-        }
-    }
-
-    pub fn parse(&mut self) -> Result<Program> {
-        let mut program = Vec::new();
-        while let Some(tok) = self.peek() {
-            match tok {
-                &Token::Fn => {
-                    self.skip();
-                    match self.next() {
-                        Some(Token::Ident(name)) => {
-                            expect!(self.next(), "'('", Token::OpenParen);
-                            expect!(self.next(), "')'", Token::CloseParen);
-                            program.push(Stat::Fn(name, Vec::new(), self.expr()?));
-                        }
-                        Some(t) => return error!(self.pos(), "function name", t),
-                        None => return error!(self.pos(), "function name", "<eof>"),
-                    }
-                }
-                &Token::Data => {
-                    self.skip();
-                    expect!(self.next(), "'['", Token::OpenBracket);
-                    if let Some(Token::Integer(pos)) = self.next() {
-                        assert!(pos >= 0, "`data` position may not be negative");
-                        expect!(self.next(), "']'", Token::CloseBracket);
-                        expect!(self.next(), "'='", Token::Equals);
-                        if let Some(Token::String(data)) = self.next() {
-                            program.push(Stat::Data(pos as u64, data));
-                        }
-                    } else {
-                        return error!(self.pos(), "expected integer, e.g. data[<int>]");
-                    }
-                }
-                &Token::Semicolon => self.skip(),
-                _ => return error!(self.pos(), "data, fn or ';'", self.next().unwrap()),
-            }
-        }
-
-        match self.next() {
-            Some(t) => error!(self.pos(), "<eof>", t),
-            None => Ok(program),
-        }
-    }
-}
+// TODO: Semantic validation
+
+use crate::{
+    diagnostics::{codes, Diagnostic},
+    lexer::{tokenize, Number, Span, Token, Tokens},
+    operators,
+};
+use serde::Serialize;
+use std::fmt;
+
+macro_rules! error {
+    ($span: expr, $e: expr, $g: expr) => {
+        Err(Diagnostic::error(
+            codes::UNEXPECTED_TOKEN,
+            format!("expected {}, got {}", $e, $g),
+            $span,
+        ))
+    };
+}
+
+/// A `///` comment where no item follows, e.g. inside a function body.
+fn stray_doc_comment(span: Span) -> Diagnostic {
+    Diagnostic::error(
+        codes::UNEXPECTED_TOKEN,
+        "doc comment not attached to an item",
+        span,
+    )
+    .with_note("use `//` for comments inside functions")
+}
+
+macro_rules! eof {
+    ($self: expr, $e: expr) => {
+        Err(Diagnostic::error(
+            codes::UNEXPECTED_EOF,
+            format!("expected {}, got <eof>", $e),
+            $self.eof_span(),
+        ))
+    };
+}
+
+pub type Result<T> = std::result::Result<T, Diagnostic>;
+
+#[derive(Debug, Serialize)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+impl Expr {
+    fn new(kind: ExprKind, span: Span) -> Expr {
+        Expr { kind, span }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ExprKind {
+    Paren(Box<Expr>),
+    Block(Vec<Expr>),
+    /// `target = value`, the target is a name or a tuple of names.
+    Assignment(Box<Expr>, Box<Expr>),
+    BinOp(Box<Expr>, String, Box<Expr>),
+    Call(Box<Expr>, Vec<Expr>),
+    Ident(String),
+    Integer(Number<i128>),
+    Float(Number<f64>),
+    /// A character literal, an integer constant holding its codepoint.
+    Char(char),
+    Return(Box<Expr>),
+    /// `(a, b)`, several values at once, e.g. the results of a function.
+    Tuple(Vec<Expr>),
+    /// `p.x`, a field of the struct `p` points to.
+    Field(Box<Expr>, String),
+}
+
+/// A type written in the source.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Type {
+    pub kind: TypeKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum TypeKind {
+    /// A named type, e.g. `i32` or a struct.
+    Name(String),
+    /// `(i32, i32)`, the type of several values.
+    Tuple(Vec<Type>),
+    /// `*Point`, an address in linear memory.
+    Pointer(Box<Type>),
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            TypeKind::Name(name) => write!(f, "{}", name),
+            TypeKind::Tuple(types) => {
+                write!(f, "(")?;
+                for (i, ty) in types.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", ty)?;
+                }
+                write!(f, ")")
+            }
+            TypeKind::Pointer(ty) => write!(f, "*{}", ty),
+        }
+    }
+}
+
+/// A name listed in a `use`, e.g. `add` in `use "math.er" { add }`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportName {
+    pub name: String,
+    pub span: Span,
+}
+
+/// A function parameter, `name: type`.
+#[derive(Debug, Serialize)]
+pub struct Param {
+    pub name: String,
+    pub ty: Type,
+    pub span: Span,
+}
+
+/// A struct field, `name: type`.
+#[derive(Debug, Serialize)]
+pub struct Field {
+    pub name: String,
+    pub ty: Type,
+    pub span: Span,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Stat {
+    pub kind: StatKind,
+    pub span: Span,
+    /// Lines of the `///` comments above the statement, without the slashes.
+    pub doc: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum StatKind {
+    /// `fn name(params) -> results body`, the results are optional.
+    Fn {
+        name: String,
+        params: Vec<Param>,
+        results: Option<Type>,
+        body: Expr,
+    },
+    /// `data[offset] = "bytes"`
+    Data { offset: u64, bytes: Vec<u8> },
+    /// `use "path.er"`, optionally importing only the listed functions
+    /// and structs.
+    Use {
+        path: String,
+        names: Option<Vec<ImportName>>,
+    },
+    /// `struct Point { x: f32, y: f32 }`, laid out in memory in order.
+    Struct { name: String, fields: Vec<Field> },
+}
+
+pub type Program = Vec<Stat>;
+
+pub struct Parser<'a> {
+    tokens: Tokens<'a>,
+    len: usize,
+    peeked: Option<Option<(Token, Span)>>,
+    prev: Span,
+    errors: Vec<Diagnostic>,
+}
+
+macro_rules! expect {
+    ($self: expr, $name: expr, $token_type: pat) => {
+        match $self.peek() {
+            Some($token_type) => $self.skip(),
+            Some(t) => {
+                let got = t.to_string();
+                return error!($self.peek_span(), $name, got);
+            }
+            None => return eof!($self, $name),
+        }
+    };
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(source: &'a dyn AsRef<str>) -> Parser<'a> {
+        Parser {
+            tokens: tokenize(source.as_ref()),
+            len: source.as_ref().len(),
+            peeked: None,
+            prev: 0..0,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Pulls the next token from the lexer. Lexical errors are reported
+    /// right away and handed on as `Token::Error`.
+    fn advance(&mut self) -> Option<(Token, Span)> {
+        match self.tokens.next()? {
+            Ok(next) => Some(next),
+            Err(e) => {
+                let span = e.span.clone();
+                self.errors.push(e.into());
+                Some((Token::Error, span))
+            }
+        }
+    }
+
+    #[inline]
+    fn peek(&mut self) -> Option<&Token> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.advance());
+        }
+        self.peeked
+            .as_ref()
+            .and_then(|p| p.as_ref())
+            .map(|(t, _)| t)
+    }
+
+    #[inline]
+    fn next(&mut self) -> Option<Token> {
+        let next = match self.peeked.take() {
+            Some(v) => v,
+            None => self.advance(),
+        };
+
+        next.map(|(t, span)| {
+            self.prev = span;
+            t
+        })
+    }
+
+    #[inline]
+    fn skip(&mut self) {
+        self.next();
+    }
+
+    /// Span of the next token, or an empty span at the end of input.
+    fn peek_span(&mut self) -> Span {
+        self.peek();
+        match &self.peeked {
+            Some(Some((_, span))) => span.clone(),
+            _ => self.eof_span(),
+        }
+    }
+
+    /// Byte offset where the next token starts.
+    #[inline]
+    fn start(&mut self) -> usize {
+        self.peek_span().start
+    }
+
+    /// Span from `start` to the end of the last consumed token.
+    #[inline]
+    fn span_from(&self, start: usize) -> Span {
+        start..self.prev.end
+    }
+
+    /// Span of the last consumed token.
+    #[inline]
+    fn prev_span(&self) -> Span {
+        self.prev.clone()
+    }
+
+    #[inline]
+    fn eof_span(&self) -> Span {
+        self.len..self.len
+    }
+
+    fn prefixexpr(&mut self) -> Result<Expr> {
+        let start = self.start();
+        let kind = match self.peek() {
+            Some(Token::OpenParen) => {
+                self.skip();
+                let first = self.expr()?;
+                let kind = match self.peek() {
+                    Some(Token::Comma) => {
+                        let mut exprs = vec![first];
+                        while let Some(Token::Comma) = self.peek() {
+                            self.skip();
+                            exprs.push(self.expr()?);
+                        }
+                        ExprKind::Tuple(exprs)
+                    }
+                    _ => ExprKind::Paren(Box::new(first)),
+                };
+                expect!(self, "')'", Token::CloseParen);
+                kind
+            }
+            Some(&Token::Float(f)) => {
+                self.skip();
+                ExprKind::Float(f)
+            }
+            Some(&Token::Integer(i)) => {
+                self.skip();
+                ExprKind::Integer(i)
+            }
+            Some(&Token::Char(c)) => {
+                self.skip();
+                ExprKind::Char(c)
+            }
+            Some(Token::DocComment(_)) => return Err(stray_doc_comment(self.peek_span())),
+            // leave the offending token in place so recovery can decide whether to skip it
+            Some(t) => {
+                let got = t.to_string();
+                return error!(self.peek_span(), "parentheses, number, or character", got);
+            }
+            None => return eof!(self, "parentheses, number, or character"),
+        };
+        Ok(Expr::new(kind, self.span_from(start)))
+    }
+
+    fn primaryexpr(&mut self) -> Result<Expr> {
+        let start = self.start();
+        let mut base = match self.peek() {
+            Some(Token::Ident(s)) => {
+                // TODO: possibility to remove to_owned here
+                let owned = s.to_owned();
+                self.skip();
+
+                Expr::new(ExprKind::Ident(owned), self.span_from(start))
+            }
+            Some(&Token::Return) => {
+                self.skip();
+                let kind = ExprKind::Return(Box::new(self.expr()?));
+                Expr::new(kind, self.span_from(start))
+            }
+            Some(&Token::OpenBrace) => {
+                self.skip();
+                let kind = ExprKind::Block(self.block()?);
+                Expr::new(kind, self.span_from(start))
+            }
+            Some(..) => self.prefixexpr()?,
+            None => return eof!(self, "expression"),
+        };
+
+        loop {
+            match self.peek() {
+                Some(Token::OpenParen) => base = self.call(base, start)?,
+                Some(Token::Dot) => {
+                    self.skip();
+                    let name = match self.next() {
+                        Some(Token::Ident(name)) => name,
+                        Some(t) => return error!(self.prev_span(), "field name", t),
+                        None => return eof!(self, "field name"),
+                    };
+                    base = Expr::new(ExprKind::Field(Box::new(base), name), self.span_from(start));
+                }
+                _ => break,
+            }
+        }
+
+        let assignable =
+            |expr: &Expr| matches!(expr.kind, ExprKind::Ident(_) | ExprKind::Field(..));
+        match (&base.kind, self.peek()) {
+            (ExprKind::Tuple(targets), Some(&Token::Equals)) => {
+                if let Some(target) = targets.iter().find(|target| !assignable(target)) {
+                    return Err(Diagnostic::error(
+                        codes::INVALID_ASSIGNMENT,
+                        "can only assign to names and fields",
+                        target.span.clone(),
+                    ));
+                }
+            }
+            (_, Some(&Token::Equals)) if assignable(&base) => {}
+            _ => return Ok(base),
+        }
+        self.skip();
+        let kind = ExprKind::Assignment(Box::new(base), Box::new(self.expr()?));
+        Ok(Expr::new(kind, self.span_from(start)))
+    }
+
+    /// Parses the argument list of a call to `callee`, which starts at `start`.
+    fn call(&mut self, callee: Expr, start: usize) -> Result<Expr> {
+        self.skip();
+        let open = self.prev_span();
+        let mut arglist = Vec::new();
+
+        loop {
+            match self.peek() {
+                Some(&Token::CloseParen) => {
+                    self.skip();
+                    break;
+                }
+                Some(..) => {
+                    arglist.push(self.expr()?);
+
+                    match self.peek() {
+                        Some(&Token::CloseParen) => {
+                            self.skip();
+                            break;
+                        }
+                        Some(&Token::Comma) => {
+                            self.skip();
+                            continue;
+                        }
+                        Some(t) => {
+                            let got = t.to_string();
+                            return error!(self.peek_span(), "')' or ','", got);
+                        }
+                        None => {
+                            return eof!(self, "')' or ','")
+                                .map_err(|e| e.with_label(open, "argument list starts here"))
+                        }
+                    }
+                }
+                None => {
+                    return eof!(self, "argument or ')'")
+                        .map_err(|e| e.with_label(open, "argument list starts here"))
+                }
+            }
+        }
+
+        Ok(Expr::new(
+            ExprKind::Call(Box::new(callee), arglist),
+            self.span_from(start),
+        ))
+    }
+
+    fn subexpr(&mut self, mut lhs: Expr, min_prec: u8) -> Result<Expr> {
+        let mut peek = self.peek();
+        loop {
+            if let Some(Token::Op(op)) = peek {
+                let op_prec = operators::precedence(op)
+                    .unwrap_or_else(|| panic!("foreign operator {} found", op));
+
+                if op_prec >= min_prec {
+                    // TODO: possibility to remove to_owned here
+                    let owned_op = op.to_owned();
+                    self.skip();
+                    let mut rhs = self.primaryexpr()?;
+
+                    peek = self.peek();
+                    loop {
+                        if let Some(Token::Op(next_op)) = peek {
+                            let next_op_prec = operators::precedence(next_op)
+                                .unwrap_or_else(|| panic!("foreign operator {} found", next_op));
+
+                            if next_op_prec > op_prec {
+                                rhs = self.subexpr(rhs, min_prec + 1)?;
+                                peek = self.peek();
+                                continue;
+                            }
+                        }
+                        break;
+                    }
+
+                    let span = lhs.span.start..rhs.span.end;
+                    lhs = Expr::new(
+                        ExprKind::BinOp(Box::new(lhs), owned_op, Box::new(rhs)),
+                        span,
+                    );
+                    continue;
+                }
+            }
+            break;
+        }
+        Ok(lhs)
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let lhs = self.primaryexpr()?;
+        self.subexpr(lhs, 0)
+    }
+
+    fn block(&mut self) -> Result<Vec<Expr>> {
+        let open = self.prev_span();
+        let mut block = Vec::new();
+        loop {
+            match self.peek() {
+                Some(Token::Fn) | Some(Token::Data) | Some(Token::Use) | Some(Token::Struct)
+                | None => return self.unclosed(open),
+                Some(Token::DocComment(_)) => {
+                    let e = stray_doc_comment(self.peek_span());
+                    self.report(e);
+                    self.skip();
+                    continue;
+                }
+                Some(..) => {}
+            }
+
+            match self.expr() {
+                Ok(expr) => block.push(expr),
+                Err(e) => {
+                    self.report(e);
+                    self.synchronize();
+                }
+            }
+
+            loop {
+                match self.peek() {
+                    Some(Token::Semicolon) => {
+                        self.skip();
+                        break;
+                    }
+                    Some(Token::CloseBrace) => {
+                        self.skip();
+                        return Ok(block);
+                    }
+                    Some(Token::Fn) | Some(Token::Data) | Some(Token::Use)
+                    | Some(Token::Struct) | None => return self.unclosed(open),
+                    Some(Token::DocComment(_)) => {
+                        let e = stray_doc_comment(self.peek_span());
+                        self.report(e);
+                        self.skip();
+                    }
+
+                    // TODO: add back semicolon error message
+                    Some(t) => {
+                        let got = t.to_string();
+                        let span = self.peek_span();
+                        self.report(Diagnostic::error(
+                            codes::UNEXPECTED_TOKEN,
+                            format!("expected '}}', got {}", got),
+                            span,
+                        ));
+                        self.synchronize();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Records a recoverable error. An error at the same place as an earlier
+    /// one is dropped, e.g. a parse error about a token that failed to lex.
+    fn report(&mut self, e: Diagnostic) {
+        if !self.errors.iter().any(|prev| prev.span == e.span) {
+            self.errors.push(e);
+        }
+    }
+
+    fn unclosed<T>(&mut self, open: Span) -> Result<T> {
+        let span = self.peek_span();
+        let e = match self.peek() {
+            Some(t) => {
+                let got = t.to_string();
+                error!(span, "'}'", got)
+            }
+            None => eof!(self, "'}'"),
+        };
+        e.map_err(|e| e.with_label(open, "unclosed delimiter"))
+    }
+
+    /// Skips tokens until one a statement or block can resume from:
+    /// `fn`, `data`, `use`, `;` or `}`. Braces opened along the way are skipped
+    /// as a whole so an inner block doesn't end the outer one.
+    fn synchronize(&mut self) {
+        let mut depth = 0usize;
+        loop {
+            match self.peek() {
+                None | Some(Token::Fn) | Some(Token::Data) | Some(Token::Use)
+                | Some(Token::Struct) => return,
+                Some(Token::Semicolon) | Some(Token::CloseBrace) if depth == 0 => return,
+                Some(Token::OpenBrace) => depth += 1,
+                Some(Token::CloseBrace) => depth -= 1,
+                Some(..) => {}
+            }
+            self.skip();
+        }
+    }
+
+    /// Parses the `{ a, b }` list of a `use`, the `{` has already been consumed.
+    fn import_names(&mut self) -> Result<Vec<ImportName>> {
+        let open = self.prev_span();
+        let mut names = Vec::new();
+        loop {
+            match self.next() {
+                Some(Token::Ident(name)) => names.push(ImportName {
+                    name,
+                    span: self.prev_span(),
+                }),
+                Some(Token::CloseBrace) if !names.is_empty() => return Ok(names),
+                Some(t) => return error!(self.prev_span(), "function name", t),
+                None => {
+                    return eof!(self, "function name")
+                        .map_err(|e| e.with_label(open, "unclosed delimiter"))
+                }
+            }
+
+            match self.next() {
+                Some(Token::Comma) => {}
+                Some(Token::CloseBrace) => return Ok(names),
+                Some(t) => return error!(self.prev_span(), "',' or '}'", t),
+                None => {
+                    return eof!(self, "',' or '}'")
+                        .map_err(|e| e.with_label(open, "unclosed delimiter"))
+                }
+            }
+        }
+    }
+
+    /// Parses the parameters of a function up to the closing `)`, the `(`
+    /// has already been consumed.
+    fn params(&mut self) -> Result<Vec<Param>> {
+        let open = self.prev_span();
+        let mut params = Vec::new();
+        loop {
+            let start = self.start();
+            match self.next() {
+                Some(Token::CloseParen) if params.is_empty() => return Ok(params),
+                Some(Token::Ident(name)) => {
+                    expect!(self, "':'", Token::Colon);
+                    let ty = self.ty()?;
+                    params.push(Param {
+                        name,
+                        ty,
+                        span: self.span_from(start),
+                    });
+                }
+                Some(t) => return error!(self.prev_span(), "parameter name", t),
+                None => {
+                    return eof!(self, "parameter name")
+                        .map_err(|e| e.with_label(open, "unclosed delimiter"))
+                }
+            }
+
+            match self.next() {
+                Some(Token::Comma) => {}
+                Some(Token::CloseParen) => return Ok(params),
+                Some(t) => return error!(self.prev_span(), "',' or ')'", t),
+                None => {
+                    return eof!(self, "',' or ')'")
+                        .map_err(|e| e.with_label(open, "unclosed delimiter"))
+                }
+            }
+        }
+    }
+
+    fn ty(&mut self) -> Result<Type> {
+        let start = self.start();
+        let kind = match self.next() {
+            Some(Token::Ident(name)) => TypeKind::Name(name),
+            Some(Token::OpenParen) => {
+                let open = self.prev_span();
+                let mut types = vec![self.ty()?];
+                loop {
+                    match self.next() {
+                        Some(Token::Comma) => types.push(self.ty()?),
+                        Some(Token::CloseParen) => break,
+                        Some(t) => return error!(self.prev_span(), "',' or ')'", t),
+                        None => {
+                            return eof!(self, "',' or ')'")
+                                .map_err(|e| e.with_label(open, "unclosed delimiter"))
+                        }
+                    }
+                }
+                // `(T)` is just `T`, like a parenthesised expression
+                if types.len() == 1 {
+                    return Ok(types.remove(0));
+                }
+                TypeKind::Tuple(types)
+            }
+            Some(Token::Op(op)) if op == "*" => TypeKind::Pointer(Box::new(self.ty()?)),
+            Some(t) => return error!(self.prev_span(), "type", t),
+            None => return eof!(self, "type"),
+        };
+        Ok(Type {
+            kind,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Parses the fields of a struct up to the closing `}`, the `{` has
+    /// already been consumed.
+    fn fields(&mut self) -> Result<Vec<Field>> {
+        let open = self.prev_span();
+        let mut fields = Vec::new();
+        loop {
+            let start = self.start();
+            match self.next() {
+                Some(Token::CloseBrace) => return Ok(fields),
+                Some(Token::Ident(name)) => {
+                    expect!(self, "':'", Token::Colon);
+                    let ty = self.ty()?;
+                    fields.push(Field {
+                        name,
+                        ty,
+                        span: self.span_from(start),
+                    });
+                }
+                Some(t) => return error!(self.prev_span(), "field name or '}'", t),
+                None => {
+                    return eof!(self, "field name or '}'")
+                        .map_err(|e| e.with_label(open, "unclosed delimiter"))
+                }
+            }
+
+            match self.next() {
+                Some(Token::Comma) => {}
+                Some(Token::CloseBrace) => return Ok(fields),
+                Some(t) => return error!(self.prev_span(), "',' or '}'", t),
+                None => {
+                    return eof!(self, "',' or '}'")
+                        .map_err(|e| e.with_label(open, "unclosed delimiter"))
+                }
+            }
+        }
+    }
+
+    fn stat(&mut self) -> Result<Stat> {
+        let mut doc = Vec::new();
+        while let Some(Token::DocComment(line)) = self.peek() {
+            doc.push(line.to_owned());
+            self.skip();
+        }
+
+        let start = self.start();
+        let kind = match self.peek() {
+            Some(Token::Fn) => {
+                self.skip();
+                match self.next() {
+                    Some(Token::Ident(name)) => {
+                        expect!(self, "'('", Token::OpenParen);
+                        let params = self.params()?;
+                        let results = match self.peek() {
+                            Some(Token::Arrow) => {
+                                self.skip();
+                                Some(self.ty()?)
+                            }
+                            _ => None,
+                        };
+                        StatKind::Fn {
+                            name,
+                            params,
+                            results,
+                            body: self.expr()?,
+                        }
+                    }
+                    Some(t) => return error!(self.prev_span(), "function name", t),
+                    None => return eof!(self, "function name"),
+                }
+            }
+            Some(Token::Data) => {
+                self.skip();
+                expect!(self, "'['", Token::OpenBracket);
+                let pos = match self.next() {
+                    Some(Token::Integer(pos)) if pos.value < 0 => {
+                        return Err(Diagnostic::error(
+                            codes::NEGATIVE_DATA_OFFSET,
+                            "`data` position may not be negative",
+                            self.prev_span(),
+                        ))
+                    }
+                    Some(Token::Integer(pos)) => pos.value as u64,
+                    Some(t) => return error!(self.prev_span(), "integer, e.g. data[<int>]", t),
+                    None => return eof!(self, "integer, e.g. data[<int>]"),
+                };
+                expect!(self, "']'", Token::CloseBracket);
+                expect!(self, "'='", Token::Equals);
+                match self.next() {
+                    Some(Token::String(data)) => StatKind::Data {
+                        offset: pos,
+                        bytes: data.into_bytes(),
+                    },
+                    Some(Token::ByteString(data)) => StatKind::Data {
+                        offset: pos,
+                        bytes: data,
+                    },
+                    Some(t) => return error!(self.prev_span(), "string or byte string", t),
+                    None => return eof!(self, "string or byte string"),
+                }
+            }
+            Some(Token::Struct) => {
+                self.skip();
+                let name = match self.next() {
+                    Some(Token::Ident(name)) => name,
+                    Some(t) => return error!(self.prev_span(), "struct name", t),
+                    None => return eof!(self, "struct name"),
+                };
+                expect!(self, "'{'", Token::OpenBrace);
+                StatKind::Struct {
+                    name,
+                    fields: self.fields()?,
+                }
+            }
+            Some(Token::Use) => {
+                self.skip();
+                let path = match self.next() {
+                    Some(Token::String(path)) => path,
+                    Some(t) => return error!(self.prev_span(), "path string", t),
+                    None => return eof!(self, "path string"),
+                };
+                let names = match self.peek() {
+                    Some(Token::OpenBrace) => {
+                        self.skip();
+                        Some(self.import_names()?)
+                    }
+                    _ => None,
+                };
+                StatKind::Use { path, names }
+            }
+            next => {
+                let expected = if doc.is_empty() {
+                    "data, fn, struct, use or ';'"
+                } else {
+                    "data, fn, struct or use after doc comment"
+                };
+                return match next {
+                    Some(t) => {
+                        let got = t.to_string();
+                        error!(self.peek_span(), expected, got)
+                    }
+                    None => eof!(self, expected),
+                };
+            }
+        };
+
+        Ok(Stat {
+            kind,
+            span: self.span_from(start),
+            doc,
+        })
+    }
+
+    /// Parses the whole input, recovering from errors so that every one of
+    /// them is reported. The program contains whatever could be parsed.
+    pub fn parse(&mut self) -> (Program, Vec<Diagnostic>) {
+        let mut program = Vec::new();
+        loop {
+            match self.peek() {
+                None => break,
+                Some(Token::Semicolon) => self.skip(),
+                Some(..) => match self.stat() {
+                    Ok(stat) => program.push(stat),
+                    Err(e) => {
+                        self.report(e);
+                        self.synchronize();
+                        // a stray '}' can't resume anything at the top level
+                        if let Some(Token::CloseBrace) = self.peek() {
+                            self.skip();
+                        }
+                    }
+                },
+            }
+        }
+
+        // lexical errors are reported as soon as a token is peeked
+        let mut errors = std::mem::take(&mut self.errors);
+        errors.sort_by_key(|e| e.span.start);
+        (program, errors)
+    }
+}
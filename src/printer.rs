@@ -0,0 +1,345 @@
+use crate::{
+    lexer::{tokenize, Span, Token},
+    parser::{Expr, ExprKind, Program, Stat, StatKind},
+};
+use std::{iter::Peekable, vec};
+
+const INDENT: &str = "    ";
+
+/// A comment the parser doesn't keep, or a `///` comment, which is written
+/// back as is rather than from [`Stat::doc`] so it stays in place.
+struct Comment {
+    span: Span,
+    /// Whether the comment runs to the end of its line.
+    line: bool,
+}
+
+/// Finds every comment in `source`. Anything between two tokens that isn't
+/// whitespace has to be a comment, since the source parsed.
+fn comments(source: &str) -> Vec<Comment> {
+    let mut comments = Vec::new();
+    let mut pos = 0;
+    for token in tokenize(source) {
+        let (token, span) = match token {
+            Ok(token) => token,
+            Err(e) => (Token::Error, e.span),
+        };
+        gap_comments(source, pos..span.start, &mut comments);
+        if let Token::DocComment(_) = token {
+            comments.push(Comment {
+                span: span.clone(),
+                line: true,
+            });
+        }
+        pos = span.end;
+    }
+    gap_comments(source, pos..source.len(), &mut comments);
+    comments
+}
+
+fn gap_comments(source: &str, gap: Span, comments: &mut Vec<Comment>) {
+    let mut i = gap.start;
+    while i < gap.end {
+        let rest = &source[i..gap.end];
+        let (len, line) = if rest.starts_with("//") {
+            (rest.find('\n').unwrap_or(rest.len()), true)
+        } else if rest.starts_with("/*") {
+            (block_comment_len(rest), false)
+        } else {
+            i += rest.chars().next().map_or(1, char::len_utf8);
+            continue;
+        };
+        comments.push(Comment {
+            span: i..i + len,
+            line,
+        });
+        i += len;
+    }
+}
+
+/// Length of the nested `/* */` comment at the start of `s`.
+fn block_comment_len(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match &bytes[i..] {
+            [b'/', b'*', ..] => {
+                depth += 1;
+                i += 2;
+            }
+            [b'*', b'/', ..] => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+struct Printer<'a> {
+    source: &'a str,
+    comments: Peekable<vec::IntoIter<Comment>>,
+    out: String,
+    indent: usize,
+}
+
+impl<'a> Printer<'a> {
+    /// Writes `s`, indenting first if it starts a line.
+    fn write(&mut self, s: &str) {
+        if self.out.is_empty() || self.out.ends_with('\n') {
+            for _ in 0..self.indent {
+                self.out.push_str(INDENT);
+            }
+        }
+        self.out.push_str(s);
+    }
+
+    fn newline(&mut self) {
+        // a block comment may have left a space for code that never came
+        let end = self.out.trim_end_matches(' ').len();
+        self.out.truncate(end);
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    fn blank_line(&mut self) {
+        self.newline();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    /// Writes the comments that come before `pos` in the source. A comment
+    /// that shares its line with code stays at the end of that line.
+    fn comments_before(&mut self, pos: usize) {
+        while let Some(comment) = self.comments.next_if(|c| c.span.start < pos) {
+            let source = self.source;
+            let text = source[comment.span.clone()].trim_end();
+            let line_start = source[..comment.span.start]
+                .rfind('\n')
+                .map_or(0, |i| i + 1);
+
+            if source[line_start..comment.span.start].trim().is_empty() {
+                self.newline();
+                self.write(text);
+                let after = source[comment.span.end..].trim_start_matches([' ', '\t', '\r']);
+                if comment.line || after.is_empty() || after.starts_with('\n') {
+                    self.newline();
+                } else {
+                    self.out.push(' ');
+                }
+            } else {
+                let end = self.out.trim_end_matches('\n').len();
+                let newlines = self.out.split_off(end);
+                if !self.out.is_empty() && !self.out.ends_with(' ') {
+                    self.out.push(' ');
+                }
+                self.out.push_str(text);
+                if newlines.is_empty() {
+                    self.out.push(if comment.line { '\n' } else { ' ' });
+                } else {
+                    self.out.push_str(&newlines);
+                }
+            }
+        }
+    }
+
+    /// Writes a token of `span` exactly as it appears in the source, so
+    /// literals keep their radix, suffix and escapes.
+    fn verbatim(&mut self, span: &Span) {
+        let source = self.source;
+        self.write(&source[span.clone()]);
+    }
+
+    fn program(&mut self, program: &Program) {
+        let mut prev: Option<&Stat> = None;
+        for stat in program {
+            match (prev.map(|s| &s.kind), &stat.kind) {
+                (None, _) => {}
+                // consecutive data and use statements stay grouped
+                (Some(StatKind::Data { .. }), StatKind::Data { .. })
+                | (Some(StatKind::Use { .. }), StatKind::Use { .. }) => self.newline(),
+                _ => self.blank_line(),
+            }
+            self.comments_before(stat.span.start);
+            self.stat(stat);
+            prev = Some(stat);
+        }
+        self.comments_before(usize::MAX);
+        self.newline();
+    }
+
+    fn stat(&mut self, stat: &Stat) {
+        match &stat.kind {
+            StatKind::Fn {
+                name,
+                params,
+                results,
+                body,
+            } => {
+                self.write("fn ");
+                self.write(name);
+                self.write("(");
+                for (i, param) in params.iter().enumerate() {
+                    if i > 0 {
+                        self.write(", ");
+                    }
+                    self.write(&param.name);
+                    self.write(": ");
+                    self.write(&param.ty.to_string());
+                }
+                self.write(") ");
+                if let Some(results) = results {
+                    self.write("-> ");
+                    self.write(&results.to_string());
+                    self.write(" ");
+                }
+                self.expr(body);
+            }
+            StatKind::Data { .. } => {
+                // the offset and the string are the only literals in the statement
+                let source = &self.source[stat.span.clone()];
+                let literals: Vec<Span> = tokenize(source)
+                    .filter_map(|t| match t {
+                        Ok((Token::Integer(_), span))
+                        | Ok((Token::String(_), span))
+                        | Ok((Token::ByteString(_), span)) => Some(span),
+                        _ => None,
+                    })
+                    .map(|span| span.start + stat.span.start..span.end + stat.span.start)
+                    .collect();
+
+                self.write("data[");
+                self.verbatim(&literals[0]);
+                self.write("] = ");
+                self.comments_before(literals[1].start);
+                self.verbatim(&literals[1]);
+                self.write(";");
+            }
+            StatKind::Use { names, .. } => {
+                let source = &self.source[stat.span.clone()];
+                let path = tokenize(source)
+                    .find_map(|t| match t {
+                        Ok((Token::String(_), span)) => Some(span),
+                        _ => None,
+                    })
+                    .map(|span| span.start + stat.span.start..span.end + stat.span.start)
+                    .expect("use statements have a path");
+
+                self.write("use ");
+                self.verbatim(&path);
+                if let Some(names) = names {
+                    let names: Vec<&str> = names.iter().map(|n| n.name.as_str()).collect();
+                    self.write(&format!(" {{ {} }}", names.join(", ")));
+                }
+                self.write(";");
+            }
+            StatKind::Struct { name, fields } => {
+                self.write("struct ");
+                self.write(name);
+                self.write(" {");
+                self.indent += 1;
+                for field in fields {
+                    self.newline();
+                    self.comments_before(field.span.start);
+                    self.write(&format!("{}: {},", field.name, field.ty));
+                }
+                self.comments_before(stat.span.end - 1);
+                self.indent -= 1;
+                if !fields.is_empty() {
+                    self.newline();
+                }
+                self.write("}");
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        self.comments_before(expr.span.start);
+        match &expr.kind {
+            ExprKind::Paren(inner) => {
+                self.write("(");
+                self.expr(inner);
+                self.write(")");
+            }
+            ExprKind::Block(exprs) => {
+                self.write("{");
+                self.indent += 1;
+                for (i, e) in exprs.iter().enumerate() {
+                    self.newline();
+                    self.expr(e);
+                    if i + 1 < exprs.len() {
+                        self.write(";");
+                    }
+                }
+                self.comments_before(expr.span.end - 1);
+                self.indent -= 1;
+                self.newline();
+                self.write("}");
+            }
+            ExprKind::Assignment(target, value) => {
+                self.expr(target);
+                self.write(" = ");
+                self.expr(value);
+            }
+            ExprKind::Tuple(exprs) => {
+                self.write("(");
+                for (i, e) in exprs.iter().enumerate() {
+                    if i > 0 {
+                        self.write(", ");
+                    }
+                    self.expr(e);
+                }
+                self.write(")");
+            }
+            ExprKind::BinOp(lhs, op, rhs) => {
+                self.expr(lhs);
+                self.write(&format!(" {} ", op));
+                self.expr(rhs);
+            }
+            ExprKind::Call(callee, args) => {
+                self.expr(callee);
+                self.write("(");
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        self.write(", ");
+                    }
+                    self.expr(arg);
+                }
+                self.write(")");
+            }
+            ExprKind::Ident(name) => self.write(name),
+            ExprKind::Integer(_) | ExprKind::Float(_) | ExprKind::Char(_) => {
+                self.verbatim(&expr.span)
+            }
+            ExprKind::Return(value) => {
+                self.write("return ");
+                self.expr(value);
+            }
+            ExprKind::Field(base, name) => {
+                self.expr(base);
+                self.write(".");
+                self.write(name);
+            }
+        }
+    }
+}
+
+/// Prints `program` in canonical style. `source` is the text it was parsed
+/// from, which supplies the comments and the spelling of literals.
+pub fn print(program: &Program, source: &str) -> String {
+    let mut printer = Printer {
+        source,
+        comments: comments(source).into_iter().peekable(),
+        out: String::new(),
+        indent: 0,
+    };
+    printer.program(program);
+    printer.out
+}
@@ -0,0 +1,376 @@
+use crate::{
+    diagnostics::{codes, Diagnostic},
+    lexer::{NumType, Span},
+    parser::{Expr, ExprKind, Field, Program, Stat, StatKind, Type, TypeKind},
+    visit::{self, Visitor},
+};
+use std::collections::{HashMap, HashSet};
+
+/// A file to resolve and the functions and structs it can see besides its
+/// own.
+pub struct Scope<'a> {
+    pub name: &'a str,
+    pub program: &'a Program,
+    /// Files in the same namespace see each other's functions.
+    pub namespace: usize,
+    /// Functions brought in with `use`.
+    pub imports: Vec<&'a str>,
+    /// Structs brought in with `use`.
+    pub struct_imports: Structs<'a>,
+}
+
+/// Structs by name, with the file defining them.
+pub type Structs<'a> = HashMap<&'a str, (usize, &'a [Field])>;
+
+/// Checks that every name refers to a visible function or to a local
+/// assigned earlier in the same function, that every type and field
+/// exists, and that no function or struct is defined twice in a namespace.
+/// Returns the diagnostics of each file, in order.
+pub fn resolve(files: &[Scope]) -> Vec<Vec<Diagnostic>> {
+    let mut errors = vec![Vec::new(); files.len()];
+    let mut namespaces: HashMap<usize, HashMap<&str, (usize, Span)>> = HashMap::new();
+    let mut struct_namespaces: HashMap<usize, HashMap<&str, (usize, Span)>> = HashMap::new();
+    let mut structs: HashMap<usize, Structs> = HashMap::new();
+    for (file, scope) in files.iter().enumerate() {
+        for stat in scope.program {
+            let (what, name, defined) = match &stat.kind {
+                StatKind::Fn { name, .. } => (
+                    "function",
+                    name,
+                    namespaces.entry(scope.namespace).or_default(),
+                ),
+                StatKind::Struct { name, fields } => {
+                    structs
+                        .entry(scope.namespace)
+                        .or_default()
+                        .entry(name)
+                        .or_insert((file, fields));
+                    (
+                        "struct",
+                        name,
+                        struct_namespaces.entry(scope.namespace).or_default(),
+                    )
+                }
+                _ => continue,
+            };
+            let (first_file, first) = match defined.get(name.as_str()) {
+                Some(first) => first.clone(),
+                None => {
+                    defined.insert(name, (file, stat.span.clone()));
+                    continue;
+                }
+            };
+
+            let e = Diagnostic::error(
+                codes::DUPLICATE_DEFINITION,
+                format!("{} `{}` is defined more than once", what, name),
+                stat.span.clone(),
+            );
+            errors[file].push(if first_file == file {
+                e.with_label(first, "first defined here")
+            } else {
+                e.with_note(format!("first defined in {}", files[first_file].name))
+            });
+        }
+    }
+
+    for structs in structs.values() {
+        for (&name, &(file, _)) in structs {
+            if contains(structs, name, name, &mut HashSet::new()) {
+                let span = struct_namespaces
+                    .values()
+                    .find_map(|defined| defined.get(name).filter(|(f, _)| *f == file))
+                    .map(|(_, span)| span.clone())
+                    .unwrap_or(0..0);
+                errors[file].push(
+                    Diagnostic::error(
+                        codes::RECURSIVE_STRUCT,
+                        format!("struct `{}` contains itself", name),
+                        span,
+                    )
+                    .with_note(format!(
+                        "hold it through a pointer instead, e.g. `*{}`",
+                        name
+                    )),
+                );
+            }
+        }
+    }
+
+    for (file, scope) in files.iter().enumerate() {
+        let mut functions: HashSet<&str> = namespaces
+            .get(&scope.namespace)
+            .map(|functions| functions.keys().copied().collect())
+            .unwrap_or_default();
+        functions.extend(&scope.imports);
+        let mut visible = structs.get(&scope.namespace).cloned().unwrap_or_default();
+        visible.extend(&scope.struct_imports);
+        let mut resolver = Resolver {
+            functions: &functions,
+            structs: &visible,
+            locals: HashSet::new(),
+            types: HashMap::new(),
+            errors: &mut errors[file],
+        };
+        for stat in scope.program {
+            resolver.locals.clear();
+            resolver.types.clear();
+            resolver.visit_stat(stat);
+        }
+        errors[file].sort_by_key(|e| e.span.start);
+    }
+    errors
+}
+
+/// Whether struct `name` holds `target` by value, directly or through the
+/// fields of its fields.
+fn contains<'a>(
+    structs: &Structs<'a>,
+    name: &'a str,
+    target: &str,
+    seen: &mut HashSet<&'a str>,
+) -> bool {
+    fn held<'a>(ty: &'a Type, names: &mut Vec<&'a str>) {
+        match &ty.kind {
+            TypeKind::Name(name) => names.push(name),
+            TypeKind::Tuple(types) => types.iter().for_each(|ty| held(ty, names)),
+            TypeKind::Pointer(_) => {}
+        }
+    }
+
+    if !seen.insert(name) {
+        return false;
+    }
+    let mut names = Vec::new();
+    if let Some((_, fields)) = structs.get(name) {
+        for field in fields.iter() {
+            held(&field.ty, &mut names);
+        }
+    }
+    names
+        .into_iter()
+        .any(|held| held == target || contains(structs, held, target, seen))
+}
+
+struct Resolver<'a> {
+    functions: &'a HashSet<&'a str>,
+    structs: &'a Structs<'a>,
+    /// Locals of the function being resolved, a local exists from its first
+    /// assignment on.
+    locals: HashSet<String>,
+    /// Declared types of the locals that have one: parameters and locals
+    /// last assigned a parameter or a field.
+    types: HashMap<String, Type>,
+    errors: &'a mut Vec<Diagnostic>,
+}
+
+impl<'a> Resolver<'a> {
+    fn resolve_type(&mut self, ty: &Type) {
+        match &ty.kind {
+            TypeKind::Name(name) => {
+                if NumType::from_name(name).is_none() && !self.structs.contains_key(name.as_str()) {
+                    self.errors.push(Diagnostic::error(
+                        codes::UNKNOWN_TYPE,
+                        format!("cannot find type `{}`", name),
+                        ty.span.clone(),
+                    ));
+                }
+            }
+            TypeKind::Tuple(types) => {
+                for ty in types {
+                    self.resolve_type(ty);
+                }
+            }
+            TypeKind::Pointer(ty) => self.resolve_type(ty),
+        }
+    }
+
+    /// The fields of the struct `ty` points to.
+    fn pointee_fields(&self, ty: &Type) -> Option<&'a [Field]> {
+        match &ty.kind {
+            TypeKind::Pointer(to) => match &to.kind {
+                TypeKind::Name(name) => self.structs.get(name.as_str()).map(|(_, f)| *f),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The type `expr` was declared with, if it is a local with a declared
+    /// type or a field of one.
+    fn declared_type(&self, expr: &Expr) -> Option<Type> {
+        match &expr.kind {
+            ExprKind::Ident(name) => self.types.get(name).cloned(),
+            ExprKind::Paren(inner) => self.declared_type(inner),
+            ExprKind::Field(base, name) => {
+                let fields = self.pointee_fields(&self.declared_type(base)?)?;
+                fields
+                    .iter()
+                    .find(|field| field.name == *name)
+                    .map(|field| field.ty.clone())
+            }
+            _ => None,
+        }
+    }
+
+    fn resolve_field(&mut self, base: &Expr, name: &str, span: Span) {
+        let ty = match self.declared_type(base) {
+            Some(ty) => ty,
+            // an unresolved name has been reported already
+            None if matches!(&base.kind, ExprKind::Ident(n)
+                if !self.locals.contains(n) && !self.functions.contains(n.as_str())) =>
+            {
+                return
+            }
+            None => {
+                self.errors.push(
+                    Diagnostic::error(
+                        codes::INVALID_FIELD_ACCESS,
+                        format!("cannot access field `{}` of a value without a declared type", name),
+                        base.span.clone(),
+                    )
+                    .with_note("fields are read through parameters or fields declared as pointers to structs, e.g. `p: *Point`"),
+                );
+                return;
+            }
+        };
+
+        match self.pointee_fields(&ty) {
+            Some(fields) => {
+                if !fields.iter().any(|field| field.name == name) {
+                    let TypeKind::Pointer(to) = &ty.kind else {
+                        unreachable!("only pointers have fields")
+                    };
+                    self.errors.push(Diagnostic::error(
+                        codes::UNKNOWN_FIELD,
+                        format!("no field `{}` on `{}`", name, to),
+                        span,
+                    ));
+                }
+            }
+            // a pointer to an unknown type has been reported already
+            None if matches!(&ty.kind, TypeKind::Pointer(to)
+                if matches!(&to.kind, TypeKind::Name(n) if NumType::from_name(n).is_none())) => {}
+            None => self.errors.push(Diagnostic::error(
+                codes::INVALID_FIELD_ACCESS,
+                format!("`{}` is not a pointer to a struct", ty),
+                base.span.clone(),
+            )),
+        }
+    }
+}
+
+impl<'a> Visitor for Resolver<'a> {
+    fn visit_stat(&mut self, stat: &Stat) {
+        match &stat.kind {
+            StatKind::Fn {
+                params,
+                results,
+                body,
+                ..
+            } => {
+                let mut seen: HashMap<&str, Span> = HashMap::new();
+                for param in params {
+                    self.resolve_type(&param.ty);
+                    match seen.get(param.name.as_str()) {
+                        Some(first) => self.errors.push(
+                            Diagnostic::error(
+                                codes::DUPLICATE_DEFINITION,
+                                format!("parameter `{}` is declared more than once", param.name),
+                                param.span.clone(),
+                            )
+                            .with_label(first.clone(), "first declared here"),
+                        ),
+                        None => {
+                            seen.insert(&param.name, param.span.clone());
+                        }
+                    }
+                    self.locals.insert(param.name.clone());
+                    self.types.insert(param.name.clone(), param.ty.clone());
+                }
+                if let Some(results) = results {
+                    self.resolve_type(results);
+                }
+                self.visit_expr(body);
+            }
+            StatKind::Struct { fields, .. } => {
+                let mut seen: HashMap<&str, Span> = HashMap::new();
+                for field in fields {
+                    self.resolve_type(&field.ty);
+                    match seen.get(field.name.as_str()) {
+                        Some(first) => self.errors.push(
+                            Diagnostic::error(
+                                codes::DUPLICATE_DEFINITION,
+                                format!("field `{}` is declared more than once", field.name),
+                                field.span.clone(),
+                            )
+                            .with_label(first.clone(), "first declared here"),
+                        ),
+                        None => {
+                            seen.insert(&field.name, field.span.clone());
+                        }
+                    }
+                }
+            }
+            StatKind::Data { .. } | StatKind::Use { .. } => {}
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Ident(name) => {
+                if !self.locals.contains(name) && !self.functions.contains(name.as_str()) {
+                    self.errors.push(Diagnostic::error(
+                        codes::UNRESOLVED_NAME,
+                        format!("cannot find `{}` in this scope", name),
+                        expr.span.clone(),
+                    ));
+                }
+            }
+            ExprKind::Assignment(target, value) => {
+                self.visit_expr(value);
+                let (targets, values) = match (&target.kind, &value.kind) {
+                    (ExprKind::Tuple(targets), ExprKind::Tuple(values)) => {
+                        if targets.len() != values.len() {
+                            self.errors.push(Diagnostic::error(
+                                codes::INVALID_ASSIGNMENT,
+                                format!(
+                                    "cannot assign {} values to {} targets",
+                                    values.len(),
+                                    targets.len()
+                                ),
+                                value.span.clone(),
+                            ));
+                        }
+                        (targets.iter().collect(), values.iter().collect())
+                    }
+                    (ExprKind::Tuple(targets), _) => (targets.iter().collect(), Vec::new()),
+                    _ => (vec![&**target], vec![&**value]),
+                };
+                // a local takes the declared type of its value, all values
+                // are read before any target is written
+                let types: Vec<Option<Type>> = (0..targets.len())
+                    .map(|i| values.get(i).and_then(|value| self.declared_type(value)))
+                    .collect();
+                for (target, ty) in targets.into_iter().zip(types) {
+                    match &target.kind {
+                        ExprKind::Ident(name) => {
+                            self.locals.insert(name.clone());
+                            match ty {
+                                Some(ty) => self.types.insert(name.clone(), ty),
+                                None => self.types.remove(name),
+                            };
+                        }
+                        _ => self.visit_expr(target),
+                    }
+                }
+            }
+            ExprKind::Field(base, name) => {
+                self.visit_expr(base);
+                let span = expr.span.end - name.len()..expr.span.end;
+                self.resolve_field(base, name, span);
+            }
+            _ => visit::walk_expr(self, expr),
+        }
+    }
+}
@@ -0,0 +1,195 @@
+//! Traversals over the AST.
+//!
+//! Each trait method defaults to the matching `walk_*` or `fold_*` function,
+//! which recurses into the children. Override the methods for the nodes a
+//! pass cares about and call the walk function to keep descending.
+
+use crate::parser::{Expr, ExprKind, Program, Stat, StatKind};
+
+/// Visits the AST by reference.
+pub trait Visitor {
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program)
+    }
+
+    fn visit_stat(&mut self, stat: &Stat) {
+        walk_stat(self, stat)
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr)
+    }
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    for stat in program {
+        visitor.visit_stat(stat);
+    }
+}
+
+pub fn walk_stat<V: Visitor + ?Sized>(visitor: &mut V, stat: &Stat) {
+    match &stat.kind {
+        StatKind::Fn { body, .. } => visitor.visit_expr(body),
+        StatKind::Data { .. } | StatKind::Use { .. } | StatKind::Struct { .. } => {}
+    }
+}
+
+pub fn walk_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &Expr) {
+    match &expr.kind {
+        ExprKind::Paren(inner) | ExprKind::Return(inner) | ExprKind::Field(inner, _) => {
+            visitor.visit_expr(inner)
+        }
+        ExprKind::Block(exprs) | ExprKind::Tuple(exprs) => {
+            for expr in exprs {
+                visitor.visit_expr(expr);
+            }
+        }
+        ExprKind::Assignment(lhs, rhs) | ExprKind::BinOp(lhs, _, rhs) => {
+            visitor.visit_expr(lhs);
+            visitor.visit_expr(rhs);
+        }
+        ExprKind::Call(callee, args) => {
+            visitor.visit_expr(callee);
+            for arg in args {
+                visitor.visit_expr(arg);
+            }
+        }
+        ExprKind::Ident(_) | ExprKind::Integer(_) | ExprKind::Float(_) | ExprKind::Char(_) => {}
+    }
+}
+
+/// Visits the AST by mutable reference, for passes that edit it in place.
+pub trait VisitorMut {
+    fn visit_program_mut(&mut self, program: &mut Program) {
+        walk_program_mut(self, program)
+    }
+
+    fn visit_stat_mut(&mut self, stat: &mut Stat) {
+        walk_stat_mut(self, stat)
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        walk_expr_mut(self, expr)
+    }
+}
+
+pub fn walk_program_mut<V: VisitorMut + ?Sized>(visitor: &mut V, program: &mut Program) {
+    for stat in program {
+        visitor.visit_stat_mut(stat);
+    }
+}
+
+pub fn walk_stat_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stat: &mut Stat) {
+    match &mut stat.kind {
+        StatKind::Fn { body, .. } => visitor.visit_expr_mut(body),
+        StatKind::Data { .. } | StatKind::Use { .. } | StatKind::Struct { .. } => {}
+    }
+}
+
+pub fn walk_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut Expr) {
+    match &mut expr.kind {
+        ExprKind::Paren(inner) | ExprKind::Return(inner) | ExprKind::Field(inner, _) => {
+            visitor.visit_expr_mut(inner)
+        }
+        ExprKind::Block(exprs) | ExprKind::Tuple(exprs) => {
+            for expr in exprs {
+                visitor.visit_expr_mut(expr);
+            }
+        }
+        ExprKind::Assignment(lhs, rhs) | ExprKind::BinOp(lhs, _, rhs) => {
+            visitor.visit_expr_mut(lhs);
+            visitor.visit_expr_mut(rhs);
+        }
+        ExprKind::Call(callee, args) => {
+            visitor.visit_expr_mut(callee);
+            for arg in args {
+                visitor.visit_expr_mut(arg);
+            }
+        }
+        ExprKind::Ident(_) | ExprKind::Integer(_) | ExprKind::Float(_) | ExprKind::Char(_) => {}
+    }
+}
+
+/// Rebuilds the AST by value, for passes that replace nodes, e.g. constant
+/// folding turning `1 + 2` into `3`.
+pub trait Fold {
+    fn fold_program(&mut self, program: Program) -> Program {
+        fold_program(self, program)
+    }
+
+    fn fold_stat(&mut self, stat: Stat) -> Stat {
+        fold_stat(self, stat)
+    }
+
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        fold_expr(self, expr)
+    }
+}
+
+pub fn fold_program<F: Fold + ?Sized>(folder: &mut F, program: Program) -> Program {
+    program
+        .into_iter()
+        .map(|stat| folder.fold_stat(stat))
+        .collect()
+}
+
+pub fn fold_stat<F: Fold + ?Sized>(folder: &mut F, stat: Stat) -> Stat {
+    let kind = match stat.kind {
+        StatKind::Fn {
+            name,
+            params,
+            results,
+            body,
+        } => StatKind::Fn {
+            name,
+            params,
+            results,
+            body: folder.fold_expr(body),
+        },
+        kind @ StatKind::Data { .. }
+        | kind @ StatKind::Use { .. }
+        | kind @ StatKind::Struct { .. } => kind,
+    };
+    Stat { kind, ..stat }
+}
+
+pub fn fold_expr<F: Fold + ?Sized>(folder: &mut F, expr: Expr) -> Expr {
+    let mut fold_box = |expr: Box<Expr>| Box::new(folder.fold_expr(*expr));
+    let kind = match expr.kind {
+        ExprKind::Paren(inner) => ExprKind::Paren(fold_box(inner)),
+        ExprKind::Assignment(target, value) => {
+            let target = fold_box(target);
+            ExprKind::Assignment(target, fold_box(value))
+        }
+        ExprKind::Return(value) => ExprKind::Return(fold_box(value)),
+        ExprKind::Field(base, name) => ExprKind::Field(fold_box(base), name),
+        ExprKind::BinOp(lhs, op, rhs) => {
+            let lhs = fold_box(lhs);
+            ExprKind::BinOp(lhs, op, fold_box(rhs))
+        }
+        ExprKind::Block(exprs) => ExprKind::Block(
+            exprs
+                .into_iter()
+                .map(|expr| folder.fold_expr(expr))
+                .collect(),
+        ),
+        ExprKind::Tuple(exprs) => ExprKind::Tuple(
+            exprs
+                .into_iter()
+                .map(|expr| folder.fold_expr(expr))
+                .collect(),
+        ),
+        ExprKind::Call(callee, args) => {
+            let callee = Box::new(folder.fold_expr(*callee));
+            ExprKind::Call(
+                callee,
+                args.into_iter().map(|arg| folder.fold_expr(arg)).collect(),
+            )
+        }
+        kind @ ExprKind::Ident(_)
+        | kind @ ExprKind::Integer(_)
+        | kind @ ExprKind::Float(_)
+        | kind @ ExprKind::Char(_) => kind,
+    };
+    Expr { kind, ..expr }
+}
@@ -0,0 +1,33 @@
+use eretria::{
+    ast::{ExprKind, StatKind},
+    compile,
+    diagnostics::codes,
+    parse_string, Options,
+};
+
+#[test]
+fn ast_is_public() {
+    let program = parse_string("fn main() 1").unwrap();
+    match &program[0].kind {
+        StatKind::Fn { name, body, .. } => {
+            assert_eq!(name, "main");
+            assert!(matches!(body.kind, ExprKind::Integer(_)));
+        }
+        kind => panic!("expected a function, got {:?}", kind),
+    }
+}
+
+#[test]
+fn compile_reports_check_errors() {
+    let errors = compile("fn main() missing", &Options::default()).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, codes::UNRESOLVED_NAME);
+}
+
+#[test]
+fn compile_without_code_generation() {
+    let mut options = Options::default();
+    options.optimization_level = 2;
+    let errors = compile("fn main() 1", &options).unwrap_err();
+    assert_eq!(errors[0].code, codes::UNSUPPORTED);
+}
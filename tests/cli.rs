@@ -0,0 +1,163 @@
+use std::{
+    env, fs,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+fn cli() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_eretria-cli"))
+}
+
+/// Writes `source` to a file unique to the calling test.
+fn source_file(name: &str, source: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("eretria-cli-{}.er", name));
+    fs::write(&path, source).unwrap();
+    path
+}
+
+#[test]
+fn build_emits_ast_to_stdout() {
+    let input = source_file("emit-ast", "fn main() 1");
+    let output = cli()
+        .args(["build", "--emit", "ast", "-o", "-"])
+        .arg(&input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("Integer"));
+}
+
+#[test]
+fn build_writes_output_file() {
+    let input = source_file("emit-tokens", "fn main() 1");
+    let out = input.with_extension("tokens");
+    let status = cli()
+        .args(["build", "--emit", "tokens", "-o"])
+        .arg(&out)
+        .arg(&input)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(fs::read_to_string(&out)
+        .unwrap()
+        .starts_with("1:1-1:3\t'fn'"));
+}
+
+#[test]
+fn build_fails_on_parse_errors() {
+    let input = source_file("broken", "fn main() {");
+    let output = cli()
+        .args(["build", "--emit", "ast", "-o", "-"])
+        .arg(&input)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("E0002"));
+}
+
+#[test]
+fn run_reports_missing_function() {
+    let input = source_file("run-missing", "fn main() 1");
+    let output = cli()
+        .args(["run", "--invoke", "start"])
+        .arg(&input)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("no function named `start`"));
+}
+
+#[test]
+fn check_reads_stdin_and_files() {
+    let helper = source_file("helper", "fn helper() 1");
+    let mut child = cli()
+        .args(["check", "-"])
+        .arg(&helper)
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"fn main() helper() + missing")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("--> <stdin>:1:22"));
+    assert!(!stderr.contains("`helper`"));
+}
+
+#[test]
+fn repl_continues_open_delimiters() {
+    let mut child = cli()
+        .arg("repl")
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"fn one() {\n    1\n}\none(\n)\nmissing\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(stderr.matches("cannot evaluate").count(), 1);
+    assert!(stderr.contains("cannot find `missing` in this scope"));
+}
+
+#[test]
+fn repl_reads_used_files_and_reports_against_the_entry() {
+    let lib = source_file("repl-lib", "fn sq(x: i32) x * x");
+    let mut child = cli()
+        .arg("repl")
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    write!(
+        child.stdin.take().unwrap(),
+        "use {:?}\nfn one() 1\nsq(3)\nmissing\n",
+        lib
+    )
+    .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(stderr.matches("cannot evaluate").count(), 1);
+    assert!(!stderr.contains("E0015"));
+    assert!(stderr.contains("--> <repl>:1:1\n"));
+    assert!(!stderr.contains("__repl"));
+}
+
+#[test]
+fn repl_continues_after_doc_comments() {
+    let mut child = cli()
+        .arg("repl")
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"/// Returns two.\nfn two() 2\ntwo()\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(
+        stderr,
+        "error: cannot evaluate: code generation is not implemented yet\n"
+    );
+}
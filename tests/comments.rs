@@ -0,0 +1,92 @@
+use eretria::{
+    diagnostics::codes,
+    lexer::{tokenize, LexErrorKind, Token},
+    parse_string,
+};
+
+fn tokens(source: &str) -> Vec<Token> {
+    tokenize(source).map(|t| t.unwrap().0).collect()
+}
+
+#[test]
+fn line_comment() {
+    assert_eq!(
+        tokens("fn // comment\nmain"),
+        vec![Token::Fn, Token::Ident("main".to_owned())]
+    );
+}
+
+#[test]
+fn block_comment() {
+    assert_eq!(tokens("fn /* a */ main"), tokens("fn main"));
+}
+
+#[test]
+fn nested_block_comment() {
+    assert_eq!(tokens("fn /* a /* b */ c */ main"), tokens("fn main"));
+}
+
+#[test]
+fn unterminated_block_comment() {
+    let err = tokenize("fn /* a /* b */ main")
+        .find_map(|t| t.err())
+        .unwrap();
+    assert_eq!(err.kind, LexErrorKind::UnterminatedComment);
+    assert_eq!(err.span, 3..20);
+}
+
+#[test]
+fn doc_comment_token() {
+    assert_eq!(
+        tokens("/// hello\n//// not a doc\nfn"),
+        vec![Token::DocComment(" hello".to_owned()), Token::Fn]
+    );
+}
+
+#[test]
+fn doc_comment_attached() {
+    let program = parse_string(
+        r#"
+/// Entry point.
+///
+/// Does nothing.
+fn main() 1 // returns one
+
+// not documented
+data[0] = "hi"
+"#,
+    )
+    .unwrap();
+    assert_eq!(program[0].doc, vec![" Entry point.", "", " Does nothing."]);
+    assert!(program[1].doc.is_empty());
+}
+
+#[test]
+fn dangling_doc_comment() {
+    assert!(parse_string("fn main() 1\n/// nothing follows").is_err());
+}
+
+#[test]
+fn comments_in_expressions() {
+    assert!(parse_string("fn main() { a = 1 /* one */; // done\n b = a }").is_ok());
+}
+
+#[test]
+fn doc_comment_in_function_body() {
+    let errors =
+        parse_string("fn main() {\n    /// one\n    a = 1 /// two\n    ;a + /// three\n1 }")
+            .unwrap_err();
+    let found: Vec<_> = errors
+        .iter()
+        .map(|e| (e.message.as_str(), e.span.clone()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("doc comment not attached to an item", 16..23),
+            ("doc comment not attached to an item", 34..41),
+            ("doc comment not attached to an item", 51..60),
+        ]
+    );
+    assert_eq!(errors[0].code, codes::UNEXPECTED_TOKEN);
+}
@@ -0,0 +1,61 @@
+use eretria::{
+    diagnostics::{codes, Diagnostic, Severity},
+    parse_string,
+};
+
+#[test]
+fn structured_error() {
+    let err = &parse_string("fn main() main(1 1)").unwrap_err()[0];
+    assert_eq!(err.severity, Severity::Error);
+    assert_eq!(err.code, codes::UNEXPECTED_TOKEN);
+    assert_eq!(err.span, 17..18);
+}
+
+#[test]
+fn eof_error_labels_delimiter() {
+    let err = &parse_string("fn main() {1").unwrap_err()[0];
+    assert_eq!(err.code, codes::UNEXPECTED_EOF);
+    assert_eq!(err.span, 12..12);
+    assert_eq!(err.labels[0].span, 10..11);
+}
+
+#[test]
+fn render_snippet() {
+    let source = "fn main() main(1 1)";
+    let rendered = parse_string(source).unwrap_err()[0].render(source, "main.er", false);
+    assert_eq!(
+        rendered,
+        "error[E0001]: expected ')' or ',', got integer 1\n \
+         --> main.er:1:18\n  \
+         |\n\
+         1 | fn main() main(1 1)\n  \
+         |                  ^\n"
+    );
+}
+
+#[test]
+fn render_labels_and_notes() {
+    let source = "fn main() {\n\ta = 1;\n\n\n\tb = 2\n";
+    let rendered = Diagnostic::error(codes::UNEXPECTED_EOF, "oops", 23..24)
+        .with_label(10..11, "opened here")
+        .with_note("something")
+        .render(source, "main.er", false);
+    assert_eq!(
+        rendered,
+        "error[E0002]: oops\n \
+         --> main.er:5:2\n  \
+         |\n\
+         1 | fn main() {\n  \
+         |           - opened here\n\
+         ...\n\
+         5 | \tb = 2\n  \
+         | \t^\n  \
+         = note: something\n"
+    );
+}
+
+#[test]
+fn render_empty_source() {
+    let rendered = Diagnostic::error(codes::UNEXPECTED_EOF, "empty", 0..0).render("", "-", false);
+    assert!(rendered.contains("--> -:1:1"));
+}
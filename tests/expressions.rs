@@ -1,4 +1,4 @@
-use eretria::parse_string;
+use eretria::{diagnostics::codes, parse_string};
 
 #[test]
 fn assignment() {
@@ -30,3 +30,27 @@ fn _return() {
 fn multi_block() {
     assert!(parse_string("fn main() {a = 1; b = 2; c = 3}").is_ok());
 }
+
+#[test]
+fn tuple() {
+    assert!(parse_string("fn main() return (1, 2)").is_ok());
+}
+
+#[test]
+fn tuple_assignment() {
+    assert!(parse_string("fn main() (q, r) = divmod(7, 2)").is_ok());
+}
+
+#[test]
+fn invalid_tuple_assignment() {
+    let errors = parse_string("fn main() (q, 1) = divmod(7, 2)").unwrap_err();
+    assert_eq!(errors[0].code, codes::INVALID_ASSIGNMENT);
+    assert_eq!(errors[0].span, 14..15);
+}
+
+#[test]
+fn field_access_and_assignment() {
+    assert!(parse_string("fn f(p: *Point) { p.x = p.next.x; (p.x, p.y) = (1.0, 2.0) }").is_ok());
+    assert!(parse_string("fn f(p: *Point) p.").is_err());
+    assert!(parse_string("fn f(p: *Point) p.1").is_err());
+}
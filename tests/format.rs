@@ -0,0 +1,92 @@
+use eretria::{format_string, parse_string};
+
+/// The AST without spans, which formatting is allowed to move.
+fn shape(source: &str) -> String {
+    let debug = format!("{:?}", parse_string(source).unwrap());
+    let mut out = String::new();
+    for (i, part) in debug.split("span: ").enumerate() {
+        if i == 0 {
+            out.push_str(part);
+        } else {
+            out.push_str(part.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.'));
+        }
+    }
+    out
+}
+
+const MESSY: &str = r#"// leading comment
+data[0x10]="hi";   data[4] = b"\xFF"
+/// Adds things.
+fn main()   {a=1;b = 0xFFu8+ /* inline */ 'a'; // trailing
+  // own line
+  c =f(a,(b  +1) , {x;y}) * 2 /* before brace */ }
+fn g() return 1.5e3f32 // end
+"#;
+
+const FORMATTED: &str = r#"// leading comment
+data[0x10] = "hi";
+data[4] = b"\xFF";
+
+/// Adds things.
+fn main() {
+    a = 1;
+    b = 0xFFu8 + /* inline */ 'a'; // trailing
+    // own line
+    c = f(a, (b + 1), {
+        x;
+        y
+    }) * 2 /* before brace */
+}
+
+fn g() return 1.5e3f32 // end
+"#;
+
+#[test]
+fn canonical_output() {
+    assert_eq!(format_string(MESSY).unwrap(), FORMATTED);
+}
+
+#[test]
+fn idempotent() {
+    assert_eq!(format_string(FORMATTED).unwrap(), FORMATTED);
+}
+
+#[test]
+fn reparse_gives_same_ast() {
+    assert_eq!(shape(MESSY), shape(&format_string(MESSY).unwrap()));
+}
+
+#[test]
+fn keeps_parentheses() {
+    assert_eq!(
+        format_string("fn main() ((1+2))*3").unwrap(),
+        "fn main() ((1 + 2)) * 3\n"
+    );
+}
+
+#[test]
+fn only_comments() {
+    assert_eq!(format_string("  /* a */\n// b").unwrap(), "/* a */\n// b\n");
+}
+
+#[test]
+fn parse_errors_are_returned() {
+    assert!(format_string("fn main() {").is_err());
+}
+
+#[test]
+fn signatures_and_tuples() {
+    assert_eq!(
+        format_string("fn divmod( a:i32,b : i32 )->( i32,i32 ){(q,r)=(a/b,a-b);return (q,r)}")
+            .unwrap(),
+        "fn divmod(a: i32, b: i32) -> (i32, i32) {\n    (q, r) = (a / b, a - b);\n    return (q, r)\n}\n"
+    );
+}
+
+#[test]
+fn structs_and_fields() {
+    assert_eq!(
+        format_string("struct Point{x:f32,y : f32}struct E{}fn f(p:*Point){p.x=p . y}").unwrap(),
+        "struct Point {\n    x: f32,\n    y: f32,\n}\n\nstruct E {}\n\nfn f(p: *Point) {\n    p.x = p.y\n}\n"
+    );
+}
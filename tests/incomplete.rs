@@ -1,4 +1,4 @@
-use eretria::parse_string;
+use eretria::{is_incomplete, parse_string};
 // test incomplete errors
 
 // functions
@@ -46,3 +46,52 @@ fn no_close_call() {
 fn no_comma() {
     assert!(parse_string("fn main() main(1 1)").is_err());
 }
+
+// parameters
+
+#[test]
+fn no_open_paren() {
+    assert!(parse_string("fn main 1").is_err());
+}
+
+#[test]
+fn no_close_paren() {
+    assert!(parse_string("fn main(").is_err());
+}
+
+// data
+
+#[test]
+fn incomplete_data() {
+    assert!(parse_string("data").is_err());
+    assert!(parse_string("data[").is_err());
+    assert!(parse_string("data[0").is_err());
+    assert!(parse_string("data[0]").is_err());
+    assert!(parse_string("data[0] =").is_err());
+}
+
+// parentheses
+
+#[test]
+fn unclosed_paren() {
+    assert!(parse_string("fn main() (1").is_err());
+}
+
+// continuation lines
+
+#[test]
+fn open_delimiters_need_more_input() {
+    assert!(is_incomplete("fn main() {"));
+    assert!(is_incomplete("fn main() f(1,\n"));
+    assert!(is_incomplete("fn main() { (1 +\n"));
+    assert!(!is_incomplete("fn main() { 1 }"));
+    assert!(!is_incomplete("fn main() 1 +"));
+}
+
+#[test]
+fn doc_comments_need_their_item() {
+    assert!(is_incomplete("/// Returns two.\n"));
+    assert!(is_incomplete("/// Returns two.\n/// Always.\n"));
+    assert!(!is_incomplete("/// Returns two.\nfn two() 2\n"));
+    assert!(!is_incomplete("// just a comment\n"));
+}
@@ -0,0 +1,58 @@
+use eretria::{
+    diagnostics::{codes, Diagnostic},
+    lexer::{tokenize, NumType, Number, Token},
+    parse_string,
+};
+use serde_json::json;
+
+#[test]
+fn token_json() {
+    let (token, _) = tokenize("fn").next().unwrap().unwrap();
+    assert_eq!(serde_json::to_value(token).unwrap(), json!("fn"));
+
+    let token = Token::Integer(Number {
+        value: 255,
+        ty: Some(NumType::U8),
+    });
+    assert_eq!(
+        serde_json::to_value(token).unwrap(),
+        json!({ "integer": { "value": 255, "ty": "u8" } })
+    );
+}
+
+#[test]
+fn program_json() {
+    let program = parse_string("fn main() x").unwrap();
+    assert_eq!(
+        serde_json::to_value(&program).unwrap(),
+        json!([{
+            "kind": { "fn": {
+                "name": "main",
+                "params": [],
+                "results": null,
+                "body": {
+                    "kind": { "ident": "x" },
+                    "span": { "start": 10, "end": 11 }
+                }
+            }},
+            "span": { "start": 0, "end": 11 },
+            "doc": []
+        }])
+    );
+}
+
+#[test]
+fn diagnostic_json() {
+    let diagnostic = Diagnostic::error(codes::UNEXPECTED_EOF, "oops", 3..3).with_note("n");
+    assert_eq!(
+        serde_json::to_value(diagnostic).unwrap(),
+        json!({
+            "severity": "error",
+            "code": "E0002",
+            "message": "oops",
+            "span": { "start": 3, "end": 3 },
+            "labels": [],
+            "notes": ["n"]
+        })
+    );
+}
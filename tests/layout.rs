@@ -0,0 +1,72 @@
+use eretria::{
+    layout::{struct_layouts, FieldLayout},
+    parse_string,
+};
+
+fn fields(layout: &[FieldLayout]) -> Vec<(&str, u32, u32)> {
+    layout
+        .iter()
+        .map(|field| (field.name.as_str(), field.offset, field.size))
+        .collect()
+}
+
+#[test]
+fn point() {
+    let program = parse_string("struct Point { x: f32, y: f32 }").unwrap();
+    let layouts = struct_layouts(&program);
+    let point = &layouts["Point"];
+    assert_eq!((point.size, point.align), (8, 4));
+    assert_eq!(fields(&point.fields), vec![("x", 0, 4), ("y", 4, 4)]);
+}
+
+#[test]
+fn fields_are_aligned() {
+    let program = parse_string("struct P { a: u8, b: i32, c: u8 }").unwrap();
+    let layouts = struct_layouts(&program);
+    let p = &layouts["P"];
+    assert_eq!((p.size, p.align), (12, 4));
+    assert_eq!(
+        fields(&p.fields),
+        vec![("a", 0, 1), ("b", 4, 4), ("c", 8, 1)]
+    );
+}
+
+#[test]
+fn nested_structs_pointers_and_tuples() {
+    let program = parse_string(
+        "struct Node { value: u16, pair: (u8, f64), next: *Node }
+        struct List { len: u8, head: Node }",
+    )
+    .unwrap();
+    let layouts = struct_layouts(&program);
+    let node = &layouts["Node"];
+    assert_eq!((node.size, node.align), (32, 8));
+    assert_eq!(
+        fields(&node.fields),
+        vec![("value", 0, 2), ("pair", 8, 16), ("next", 24, 4)]
+    );
+    let list = &layouts["List"];
+    assert_eq!((list.size, list.align), (40, 8));
+    assert_eq!(fields(&list.fields), vec![("len", 0, 1), ("head", 8, 32)]);
+}
+
+#[test]
+fn recursive_and_unknown_structs_are_left_out() {
+    let program = parse_string("struct A { b: B } struct B { a: A } struct C { x: word }").unwrap();
+    assert!(struct_layouts(&program).is_empty());
+}
+
+#[test]
+fn oversized_structs_are_left_out() {
+    // every level holds 8 of the one below, 4 * 8^12 bytes in the end
+    let mut source = "struct L0 { x: u32 }".to_owned();
+    for level in 1..=12 {
+        let below = vec![format!("L{}", level - 1); 8].join(", ");
+        source.push_str(&format!(" struct L{} {{ x: ({}) }}", level, below));
+    }
+    let program = parse_string(source).unwrap();
+    let layouts = struct_layouts(&program);
+    assert_eq!(layouts["L9"].size, 1 << 29);
+    assert!(!layouts.contains_key("L10"));
+    assert!(!layouts.contains_key("L12"));
+}
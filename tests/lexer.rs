@@ -0,0 +1,130 @@
+use eretria::{
+    diagnostics::codes,
+    lexer::{tokenize, LexErrorKind, NumType, Number, Token},
+    parse_string,
+};
+
+fn errors(source: &str) -> Vec<LexErrorKind> {
+    tokenize(source)
+        .filter_map(|t| t.err())
+        .map(|e| e.kind)
+        .collect()
+}
+
+#[test]
+fn spanned_tokens() {
+    let tokens: Vec<_> = tokenize("fn main() 12").map(|t| t.unwrap()).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            (Token::Fn, 0..2),
+            (Token::Ident("main".to_owned()), 3..7),
+            (Token::OpenParen, 7..8),
+            (Token::CloseParen, 8..9),
+            (
+                Token::Integer(Number {
+                    value: 12,
+                    ty: None
+                }),
+                10..12
+            ),
+        ]
+    );
+}
+
+#[test]
+fn string_span_includes_quotes() {
+    let (token, span) = tokenize(r#"  "hi""#).next().unwrap().unwrap();
+    assert_eq!(token, Token::String("hi".to_owned()));
+    assert_eq!(span, 2..6);
+}
+
+#[test]
+fn unterminated_string() {
+    let tokens: Vec<_> = tokenize(r#"data[0] = "hi"#).collect();
+    let err = tokens.last().unwrap().as_ref().unwrap_err();
+    assert_eq!(err.kind, LexErrorKind::UnterminatedString);
+    assert_eq!(err.span, 10..13);
+}
+
+#[test]
+fn bad_escape() {
+    assert_eq!(errors(r#""\xD800""#), vec![LexErrorKind::InvalidEscape]);
+}
+
+#[test]
+fn invalid_radix_digit() {
+    assert_eq!(errors("0b102"), vec![LexErrorKind::InvalidDigit(2)]);
+    assert_eq!(errors("0x+5"), vec![LexErrorKind::InvalidDigit(16)]);
+}
+
+#[test]
+fn unknown_radix() {
+    assert_eq!(errors("0a12"), vec![LexErrorKind::UnknownRadix('a')]);
+}
+
+#[test]
+fn integer_overflow() {
+    assert_eq!(
+        errors("99999999999999999999"),
+        vec![LexErrorKind::OutOfRange(NumType::U64)]
+    );
+    assert_eq!(
+        errors("0x10000000000000000"),
+        vec![LexErrorKind::OutOfRange(NumType::U64)]
+    );
+}
+
+#[test]
+fn negative_radix_limits() {
+    let (token, _) = tokenize("-0x8000000000000000").next().unwrap().unwrap();
+    assert_eq!(
+        token,
+        Token::Integer(Number {
+            value: i64::MIN as i128,
+            ty: None
+        })
+    );
+}
+
+#[test]
+fn lex_error_reported_once() {
+    let errors = parse_string("fn main() a = 0b12").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "invalid digit for a base 2 literal");
+}
+
+#[test]
+fn unicode_identifiers() {
+    let tokens: Vec<_> = tokenize("_x é_1 dataé").map(|t| t.unwrap().0).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Ident("_x".to_owned()),
+            Token::Ident("é_1".to_owned()),
+            Token::Ident("dataé".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn punctuation_is_not_identifier() {
+    let tokens: Vec<_> = tokenize("a#b?c!").collect();
+    assert_eq!(tokens[0], Ok((Token::Ident("a".to_owned()), 0..1)));
+    assert_eq!(tokens[1].as_ref().unwrap_err().span, 1..2);
+    assert_eq!(errors("a#b?c!"), vec![LexErrorKind::UnexpectedCharacter; 3]);
+    assert_eq!(errors("€"), vec![LexErrorKind::UnexpectedCharacter]);
+}
+
+#[test]
+fn digit_cannot_start_identifier() {
+    assert_eq!(errors("1abc"), vec![LexErrorKind::InvalidSuffix]);
+}
+
+#[test]
+fn reserved_keyword() {
+    assert_eq!(errors("if"), vec![LexErrorKind::ReservedKeyword]);
+    let err = &parse_string("fn main() let = 1").unwrap_err()[0];
+    assert_eq!(err.code, codes::RESERVED_KEYWORD);
+    assert_eq!(err.span, 10..13);
+}
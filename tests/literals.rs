@@ -1,46 +1,77 @@
-use eretria::parse_string;
-
-#[test]
-fn ident() {
-    assert!(parse_string(&"fn main() hi").is_ok());
-}
-
-#[test]
-fn number() {
-    assert!(parse_string("fn main() 23928392").is_ok());
-}
-
-#[test]
-fn float() {
-    assert!(parse_string("fn main() 100.2").is_ok());
-}
-
-#[test]
-fn negative_number() {
-    assert!(parse_string("fn main() -221111").is_ok());
-}
-
-#[test]
-fn negative_float() {
-    assert!(parse_string("fn main() -202.5").is_ok());
-}
-
-#[test]
-fn obscure_decimal_number() {
-    assert!(parse_string("fn main() 0d1234567890").is_ok());
-}
-
-#[test]
-fn binary_number() {
-    assert!(parse_string("fn main() 0b10101001").is_ok());
-}
-
-#[test]
-fn invalid_binary() {
-    assert!(parse_string("fn main() 0b1221").is_err());
-}
-
-#[test]
-fn parentheses() {
-    assert!(parse_string("fn main() (-100)").is_ok());
-}
+use eretria::parse_string;
+
+#[test]
+fn ident() {
+    assert!(parse_string("fn main() hi").is_ok());
+}
+
+#[test]
+fn number() {
+    assert!(parse_string("fn main() 23928392").is_ok());
+}
+
+#[test]
+fn float() {
+    assert!(parse_string("fn main() 100.2").is_ok());
+}
+
+#[test]
+fn negative_number() {
+    assert!(parse_string("fn main() -221111").is_ok());
+}
+
+#[test]
+fn negative_float() {
+    assert!(parse_string("fn main() -202.5").is_ok());
+}
+
+#[test]
+fn obscure_decimal_number() {
+    assert!(parse_string("fn main() 0d1234567890").is_ok());
+}
+
+#[test]
+fn binary_number() {
+    assert!(parse_string("fn main() 0b10101001").is_ok());
+}
+
+#[test]
+fn invalid_binary() {
+    assert!(parse_string("fn main() 0b1221").is_err());
+}
+
+#[test]
+fn parentheses() {
+    assert!(parse_string("fn main() (-100)").is_ok());
+}
+
+#[test]
+fn negative_binary_number() {
+    assert!(parse_string("fn main() -0b101").is_ok());
+}
+
+#[test]
+fn unknown_radix() {
+    assert!(parse_string("fn main() 0a1234").is_err());
+}
+
+#[test]
+fn incomplete_string() {
+    assert!(parse_string(r#"data[0] = "hi"#).is_err());
+}
+
+#[test]
+fn incomplete_escape() {
+    assert!(parse_string(r#"data[0] = "hi\"#).is_err());
+}
+
+#[test]
+fn invalid_escape_codepoint() {
+    assert!(parse_string(r#"data[0] = "\xD800""#).is_err());
+    assert!(parse_string(r#"data[0] = "\xFFFFFFFFFF""#).is_err());
+}
+
+#[test]
+fn non_ascii_string() {
+    assert!(parse_string(r#"data[0] = "héllo"; fn main() 1"#).is_ok());
+}
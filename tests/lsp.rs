@@ -0,0 +1,261 @@
+use serde_json::{json, Value};
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+const URI: &str = "file:///tmp/eretria-lsp-test.er";
+
+/// A language server speaking to the test over stdio.
+struct Server {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+}
+
+impl Server {
+    /// Starts a server and opens a document holding `text`.
+    fn open(text: &str) -> Server {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_eretria-lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut server = Server {
+            stdin: child.stdin.take().unwrap(),
+            stdout: BufReader::new(child.stdout.take().unwrap()),
+            child,
+            next_id: 0,
+        };
+        server.request("initialize", json!({ "capabilities": {} }));
+        server.notify("initialized", json!({}));
+        server.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": { "uri": URI, "languageId": "eretria", "version": 0, "text": text }
+            }),
+        );
+        server
+    }
+
+    fn send(&mut self, message: Value) {
+        let body = message.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            self.stdout.read_line(&mut line).unwrap();
+            match line.trim_end().strip_prefix("Content-Length: ") {
+                Some(n) => length = n.parse().unwrap(),
+                None if line.trim_end().is_empty() => break,
+                None => {}
+            }
+        }
+        let mut body = vec![0; length];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    /// Sends a request and returns its result, skipping notifications.
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        loop {
+            let message = self.receive();
+            if message["id"] == id {
+                return message["result"].clone();
+            }
+        }
+    }
+
+    /// Waits for the diagnostics of the open document.
+    fn diagnostics(&mut self) -> Value {
+        loop {
+            let message = self.receive();
+            if message["method"] == "textDocument/publishDiagnostics" {
+                return message["params"]["diagnostics"].clone();
+            }
+        }
+    }
+
+    fn at(&mut self, method: &str, line: u32, character: u32) -> Value {
+        self.request(
+            method,
+            json!({
+                "textDocument": { "uri": URI },
+                "position": { "line": line, "character": character }
+            }),
+        )
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+    }
+}
+
+#[test]
+fn publishes_diagnostics() {
+    let mut server = Server::open("fn main() {\n    x\n}");
+    let diagnostics = server.diagnostics();
+    assert_eq!(diagnostics[0]["code"], "E0013");
+    assert_eq!(
+        diagnostics[0]["range"],
+        json!({ "start": { "line": 1, "character": 4 }, "end": { "line": 1, "character": 5 } })
+    );
+
+    server.notify(
+        "textDocument/didChange",
+        json!({
+            "textDocument": { "uri": URI, "version": 1 },
+            "contentChanges": [{ "text": "fn main() 1" }]
+        }),
+    );
+    assert_eq!(server.diagnostics(), json!([]));
+}
+
+#[test]
+fn hover_shows_signature_and_literal_types() {
+    let mut server = Server::open("/// Returns one.\nfn one() 1\nfn main() { a = one() + 2u8; a }");
+    let hover = server.at("textDocument/hover", 2, 17);
+    assert_eq!(
+        hover["contents"]["value"],
+        "```eretria\nfn one()\n```\n\nReturns one."
+    );
+    let hover = server.at("textDocument/hover", 2, 24);
+    assert_eq!(hover["contents"]["value"], "```eretria\nu8\n```");
+    let hover = server.at("textDocument/hover", 2, 29);
+    assert_eq!(
+        hover["contents"]["value"],
+        "```eretria\na\n```\nlocal of `main`"
+    );
+}
+
+#[test]
+fn goes_to_definitions() {
+    let mut server = Server::open("fn add() {\n    c = 1;\n    c + 2\n}\nfn main() add()");
+    let location = server.at("textDocument/definition", 2, 4);
+    assert_eq!(
+        location["range"],
+        json!({ "start": { "line": 1, "character": 4 }, "end": { "line": 1, "character": 5 } })
+    );
+    let location = server.at("textDocument/definition", 4, 11);
+    assert_eq!(
+        location["range"],
+        json!({ "start": { "line": 0, "character": 3 }, "end": { "line": 0, "character": 6 } })
+    );
+}
+
+#[test]
+fn lists_symbols_and_semantic_tokens() {
+    let mut server = Server::open("data[0] = \"hi\";\nfn main() { x = 1; x }");
+    let symbols = server.request(
+        "textDocument/documentSymbol",
+        json!({ "textDocument": { "uri": URI } }),
+    );
+    assert_eq!(symbols[0]["name"], "data[0]");
+    assert_eq!(symbols[1]["name"], "main");
+    assert_eq!(symbols[1]["detail"], "fn main()");
+
+    let tokens = server.request(
+        "textDocument/semanticTokens/full",
+        json!({ "textDocument": { "uri": URI } }),
+    );
+    // `fn`, `main`, `x`, `=`, `1`, `x` on the second line
+    let data: Vec<u64> = tokens["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|n| n.as_u64().unwrap())
+        .collect();
+    assert_eq!(
+        data[data.len() - 30..],
+        [
+            1, 0, 2, 0, 0, 0, 3, 4, 1, 0, 0, 9, 1, 3, 0, 0, 2, 1, 6, 0, 0, 2, 1, 4, 0, 0, 3, 1, 3,
+            0
+        ]
+    );
+}
+
+#[test]
+fn structs() {
+    let mut server =
+        Server::open("/// A point.\nstruct Point { x: f32, y: f32 }\nfn f(p: *Point) p.x");
+    let hover = server.at("textDocument/hover", 2, 10);
+    assert_eq!(
+        hover["contents"]["value"],
+        "```eretria\nstruct Point\n```\nsize 8, align 4\n- `x`: offset 0, size 4\n- `y`: offset 4, size 4\n\nA point."
+    );
+    let location = server.at("textDocument/definition", 2, 10);
+    assert_eq!(
+        location["range"],
+        json!({ "start": { "line": 1, "character": 7 }, "end": { "line": 1, "character": 12 } })
+    );
+
+    let symbols = server.request(
+        "textDocument/documentSymbol",
+        json!({ "textDocument": { "uri": URI } }),
+    );
+    assert_eq!(symbols[0]["name"], "Point");
+    assert_eq!(symbols[0]["children"][1]["name"], "y");
+    assert_eq!(symbols[0]["children"][1]["detail"], "f32");
+}
+
+#[test]
+fn fields_are_properties() {
+    let mut server = Server::open("struct P { x: f32 }\nfn f(p: *P) p.x");
+    let tokens = server.request(
+        "textDocument/semanticTokens/full",
+        json!({ "textDocument": { "uri": URI } }),
+    );
+    let data: Vec<u64> = tokens["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|n| n.as_u64().unwrap())
+        .collect();
+    // (line, column, type) of every token, 9 is PROPERTY
+    let mut line = 0;
+    let mut column = 0;
+    let mut properties = Vec::new();
+    for token in data.chunks(5) {
+        if token[0] > 0 {
+            column = 0;
+        }
+        line += token[0];
+        column += token[1];
+        if token[3] == 9 {
+            properties.push((line, column));
+        }
+    }
+    assert_eq!(properties, [(0, 11), (1, 14)]);
+
+    let hover = server.at("textDocument/hover", 0, 11);
+    assert_eq!(
+        hover["contents"]["value"],
+        "```eretria\nx\n```\nfield of `P`"
+    );
+}
+
+#[test]
+fn survives_malformed_notifications() {
+    let mut server = Server::open("fn main() 1");
+    server.notify("textDocument/didOpen", json!({ "textDocument": 1 }));
+    server.notify("textDocument/didChange", json!({}));
+    let symbols = server.request(
+        "textDocument/documentSymbol",
+        json!({ "textDocument": { "uri": URI } }),
+    );
+    assert_eq!(symbols[0]["name"], "main");
+}
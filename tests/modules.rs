@@ -0,0 +1,212 @@
+use eretria::{
+    check_sources,
+    diagnostics::{codes, Diagnostic},
+    format_string, import_path, parse_string, Source, StatKind,
+};
+use std::{collections::HashMap, io};
+
+/// Checks `main.er` against an in-memory file system.
+fn check(main: &str, files: &[(&str, &str)]) -> (Vec<String>, Vec<Vec<Diagnostic>>) {
+    let files: HashMap<_, _> = files.iter().copied().collect();
+    let root = Source {
+        name: "main.er".to_owned(),
+        text: main.to_owned(),
+    };
+    let (sources, errors) = check_sources(vec![root], |path| {
+        files
+            .get(path)
+            .map(|text| text.to_string())
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    });
+    (sources.into_iter().map(|s| s.name).collect(), errors)
+}
+
+#[test]
+fn use_statement() {
+    let program = parse_string(r#"use "lib/math.er" { add, mul }; use "io.er";"#).unwrap();
+    match &program[0].kind {
+        StatKind::Use {
+            path,
+            names: Some(names),
+        } => {
+            assert_eq!(path, "lib/math.er");
+            let names: Vec<_> = names
+                .iter()
+                .map(|n| (n.name.as_str(), n.span.clone()))
+                .collect();
+            assert_eq!(names, [("add", 20..23), ("mul", 25..28)]);
+        }
+        kind => panic!("unexpected statement {:?}", kind),
+    }
+    assert!(matches!(&program[1].kind, StatKind::Use { path, names: None } if path == "io.er"));
+}
+
+#[test]
+fn malformed_use() {
+    assert!(parse_string("use math").is_err());
+    assert!(parse_string(r#"use "a.er" {}"#).is_err());
+    assert!(parse_string(r#"use "a.er" { a b }"#).is_err());
+}
+
+#[test]
+fn imports_all_functions() {
+    let (names, errors) = check(
+        r#"use "lib/math.er"; fn main() add(1, 2)"#,
+        &[("lib/math.er", "fn add() 1 fn sub() 2")],
+    );
+    assert_eq!(names, vec!["main.er", "lib/math.er"]);
+    assert_eq!(errors, vec![vec![], vec![]]);
+}
+
+#[test]
+fn selective_import() {
+    let (_, errors) = check(
+        r#"use "math.er" { add }; fn main() add(1) + sub(2)"#,
+        &[("math.er", "fn add() 1 fn sub() 2")],
+    );
+    assert_eq!(errors[0].len(), 1);
+    assert_eq!(errors[0][0].code, codes::UNRESOLVED_NAME);
+    assert_eq!(errors[0][0].span, 42..45);
+}
+
+#[test]
+fn unknown_import() {
+    let (_, errors) = check(r#"use "math.er" { pow };"#, &[("math.er", "fn add() 1")]);
+    assert_eq!(errors[0][0].code, codes::UNRESOLVED_IMPORT);
+    assert_eq!(
+        errors[0][0].message,
+        "no function or struct `pow` in `math.er`"
+    );
+    assert_eq!(errors[0][0].span, 16..19);
+}
+
+#[test]
+fn imported_files_have_their_own_namespace() {
+    let (_, errors) = check(
+        r#"use "a.er"; fn helper() 1"#,
+        &[("a.er", "fn f() helper()")],
+    );
+    assert!(errors[0].is_empty());
+    assert_eq!(errors[1][0].code, codes::UNRESOLVED_NAME);
+}
+
+#[test]
+fn paths_are_relative_to_the_using_file() {
+    let (names, errors) = check(
+        r#"use "lib/a.er";"#,
+        &[("lib/a.er", r#"use "../util/b.er";"#), ("util/b.er", "")],
+    );
+    assert_eq!(names, vec!["main.er", "lib/a.er", "util/b.er"]);
+    assert!(errors.iter().all(Vec::is_empty));
+}
+
+#[test]
+fn missing_file() {
+    let (_, errors) = check(r#"use "nope.er";"#, &[]);
+    assert_eq!(errors[0][0].code, codes::IMPORT_FAILED);
+    assert_eq!(errors[0][0].span, 0..13);
+}
+
+#[test]
+fn import_cycle() {
+    let (_, errors) = check(
+        r#"use "a.er";"#,
+        &[("a.er", r#"use "b.er";"#), ("b.er", r#"use "a.er";"#)],
+    );
+    let cycles: Vec<_> = errors.iter().flatten().collect();
+    assert_eq!(cycles.len(), 1);
+    assert_eq!(cycles[0].code, codes::IMPORT_CYCLE);
+    assert_eq!(cycles[0].message, "`use` cycle: a.er -> b.er -> a.er");
+    assert!(errors[2].contains(cycles[0]));
+}
+
+#[test]
+fn diagnostics_stay_with_their_file() {
+    let (_, errors) = check(r#"use "a.er";"#, &[("a.er", "fn f() {")]);
+    assert!(errors[0].is_empty());
+    assert_eq!(errors[1][0].code, codes::UNEXPECTED_EOF);
+}
+
+#[test]
+fn format_use() {
+    assert_eq!(
+        format_string("use  \"a.er\"{x,y}\nuse \"b.er\"\nfn main() 1").unwrap(),
+        "use \"a.er\" { x, y };\nuse \"b.er\";\n\nfn main() 1\n"
+    );
+}
+
+#[test]
+fn imports_that_clash() {
+    let (_, errors) = check(
+        r#"use "lib.er" { sq }; fn sq() 1 fn main() sq()"#,
+        &[("lib.er", "fn sq() 2")],
+    );
+    assert_eq!(errors[0].len(), 1);
+    assert_eq!(errors[0][0].code, codes::DUPLICATE_DEFINITION);
+    assert_eq!(
+        errors[0][0].message,
+        "function `sq` is imported from `lib.er` but also defined here"
+    );
+    assert_eq!(errors[0][0].span, 15..17);
+
+    let (_, errors) = check(
+        r#"use "a.er"; use "b.er"; use "a.er"; fn main() f()"#,
+        &[("a.er", "fn f() 1"), ("b.er", "fn f() 2 fn g() 3")],
+    );
+    assert_eq!(errors[0].len(), 1);
+    assert_eq!(
+        errors[0][0].message,
+        "function `f` is imported from both `a.er` and `b.er`"
+    );
+    assert_eq!(errors[0][0].span, 12..22);
+}
+
+#[test]
+fn import_paths() {
+    assert_eq!(import_path("lib/a.er", "./b.er"), "lib/b.er");
+    assert_eq!(import_path("lib/a.er", "../util/b.er"), "util/b.er");
+    assert_eq!(import_path("a.er", "../b.er"), "../b.er");
+}
+
+#[test]
+fn structs_are_imported() {
+    let geo = "struct Point { x: f32, y: f32 } fn origin() 0";
+    let source = "fn len(p: *Point) p.x + p.y";
+    let (_, errors) = check(&format!(r#"use "geo.er"; {}"#, source), &[("geo.er", geo)]);
+    assert_eq!(errors, vec![vec![], vec![]]);
+    let (_, errors) = check(
+        &format!(r#"use "geo.er" {{ Point }}; {}"#, source),
+        &[("geo.er", geo)],
+    );
+    assert_eq!(errors, vec![vec![], vec![]]);
+
+    let (_, errors) = check(
+        r#"use "geo.er" { Point }; fn f(p: *Point) p.z"#,
+        &[("geo.er", geo)],
+    );
+    assert_eq!(errors[0][0].code, codes::UNKNOWN_FIELD);
+
+    let (_, errors) = check(
+        r#"use "geo.er"; use "other.er" { Point }; struct Point {}"#,
+        &[("geo.er", geo), ("other.er", "struct Point {}")],
+    );
+    let found: Vec<_> = errors[0]
+        .iter()
+        .map(|e| (e.code, e.message.as_str(), e.span.clone()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (
+                codes::DUPLICATE_DEFINITION,
+                "struct `Point` is imported from `geo.er` but also defined here",
+                0..12
+            ),
+            (
+                codes::DUPLICATE_DEFINITION,
+                "struct `Point` is imported from `other.er` but also defined here",
+                31..36
+            ),
+        ]
+    );
+}
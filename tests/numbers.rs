@@ -0,0 +1,105 @@
+use eretria::{
+    diagnostics::codes,
+    lexer::{tokenize, LexErrorKind, NumType, Number, Token},
+    parse_string,
+};
+
+fn token(source: &str) -> Token {
+    let mut tokens = tokenize(source);
+    let (token, span) = tokens.next().unwrap().unwrap();
+    assert_eq!(span, 0..source.len());
+    assert!(tokens.next().is_none());
+    token
+}
+
+fn int(value: i128, ty: Option<NumType>) -> Token {
+    Token::Integer(Number { value, ty })
+}
+
+fn float(value: f64, ty: Option<NumType>) -> Token {
+    Token::Float(Number { value, ty })
+}
+
+fn error(source: &str) -> LexErrorKind {
+    tokenize(source).find_map(|t| t.err()).unwrap().kind
+}
+
+#[test]
+fn full_width_integers() {
+    assert_eq!(token("0xFFFFFFFFFFFFFFFF"), int(u64::MAX as i128, None));
+    assert_eq!(token("18446744073709551615"), int(u64::MAX as i128, None));
+    assert_eq!(token("-9223372036854775808"), int(i64::MIN as i128, None));
+}
+
+#[test]
+fn integer_suffixes() {
+    assert_eq!(token("255u8"), int(255, Some(NumType::U8)));
+    assert_eq!(token("-128i8"), int(-128, Some(NumType::I8)));
+    assert_eq!(token("0xFFu8"), int(255, Some(NumType::U8)));
+    assert_eq!(token("0b1010i32"), int(10, Some(NumType::I32)));
+    assert_eq!(token("0u64"), int(0, Some(NumType::U64)));
+}
+
+#[test]
+fn digit_separators() {
+    assert_eq!(token("1_000_000"), int(1_000_000, None));
+    assert_eq!(token("0xFF_FF"), int(0xFFFF, None));
+    assert_eq!(token("1_000.5"), float(1000.5, None));
+}
+
+#[test]
+fn float_exponents() {
+    assert_eq!(token("1e-9"), float(1e-9, None));
+    assert_eq!(token("2.5E3"), float(2500.0, None));
+    assert_eq!(token("-1e+2"), float(-100.0, None));
+    assert_eq!(token(".5"), float(0.5, None));
+}
+
+#[test]
+fn zero_with_exponent_is_not_base_9() {
+    assert_eq!(token("0e5"), float(0.0, None));
+    assert_eq!(token("0E5"), float(0.0, None));
+    assert_eq!(token("0e-5"), float(0.0, None));
+    assert_eq!(token("-0e5f32"), float(-0.0, Some(NumType::F32)));
+}
+
+#[test]
+fn float_suffixes() {
+    assert_eq!(token("1.5f32"), float(1.5, Some(NumType::F32)));
+    assert_eq!(token("2f64"), float(2.0, Some(NumType::F64)));
+    assert_eq!(token("1e3f32"), float(1000.0, Some(NumType::F32)));
+}
+
+#[test]
+fn out_of_range() {
+    assert_eq!(error("256u8"), LexErrorKind::OutOfRange(NumType::U8));
+    assert_eq!(error("-129i8"), LexErrorKind::OutOfRange(NumType::I8));
+    assert_eq!(error("-1u32"), LexErrorKind::OutOfRange(NumType::U32));
+    assert_eq!(
+        error("-9223372036854775809"),
+        LexErrorKind::OutOfRange(NumType::I64)
+    );
+    assert_eq!(error("1e40f32"), LexErrorKind::OutOfRange(NumType::F32));
+    assert_eq!(error("1e400"), LexErrorKind::OutOfRange(NumType::F64));
+}
+
+#[test]
+fn invalid_suffix() {
+    assert_eq!(error("12abc"), LexErrorKind::InvalidSuffix);
+    assert_eq!(error("1.5u8"), LexErrorKind::InvalidSuffix);
+    assert_eq!(error("1.5e"), LexErrorKind::InvalidSuffix);
+}
+
+#[test]
+fn out_of_range_diagnostic() {
+    let err = &parse_string("fn main() 300u8").unwrap_err()[0];
+    assert_eq!(err.code, codes::LITERAL_OUT_OF_RANGE);
+    assert_eq!(err.message, "literal out of range for `u8`");
+    assert_eq!(err.span, 10..15);
+    assert_eq!(err.notes, vec!["`u8` ranges from 0 to 255".to_owned()]);
+}
+
+#[test]
+fn radix_literal_stops_at_delimiter() {
+    assert!(parse_string("fn main() main(0x10);").is_ok());
+}
@@ -0,0 +1,56 @@
+use eretria::{parse_partial, parse_string, StatKind};
+
+#[test]
+fn multiple_errors() {
+    let errors = parse_string("fn a() 1 +; fn b() (; fn c() 3").unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].span.start < errors[1].span.start);
+}
+
+#[test]
+fn partial_program() {
+    let (program, errors) = parse_partial("fn a() 1 +; fn b() 2; data[0] = \"hi\"");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(program.len(), 2);
+}
+
+#[test]
+fn recover_inside_block() {
+    let (program, errors) = parse_partial("fn main() {a = ; b = 2; c = )} fn other() 1");
+    assert_eq!(errors.len(), 2);
+    assert_eq!(program.len(), 2);
+    if let StatKind::Fn { body, .. } = &program[0].kind {
+        assert_eq!(body.span, 10..30);
+    } else {
+        panic!("expected function");
+    }
+}
+
+#[test]
+fn nested_block_recovery() {
+    let (program, errors) = parse_partial("fn main() {a = + {1; 2}; b = 2} fn other() 1");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(program.len(), 2);
+}
+
+#[test]
+fn stray_tokens() {
+    let (program, errors) = parse_partial("ssdjfhksjdggr 1 2 fn main() 1");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(program.len(), 1);
+}
+
+#[test]
+fn unclosed_block_at_next_function() {
+    let (program, errors) = parse_partial("fn main() {1; fn other() 1");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].labels[0].span, 10..11);
+    assert_eq!(program.len(), 1);
+}
+
+#[test]
+fn unclosed_call_keeps_block_brace() {
+    let (program, errors) = parse_partial("fn main() {a = f(1 }; fn other() 1");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(program.len(), 2);
+}
@@ -0,0 +1,202 @@
+use eretria::{
+    check_sources, check_string,
+    diagnostics::{codes, Diagnostic},
+    Source,
+};
+use std::io;
+
+/// Checks the given `(name, text)` sources, which can't `use` anything.
+fn check(sources: &[(&str, &str)]) -> Vec<Vec<Diagnostic>> {
+    let sources = sources
+        .iter()
+        .map(|&(name, text)| Source {
+            name: name.to_owned(),
+            text: text.to_owned(),
+        })
+        .collect();
+    check_sources(sources, |_| Err(io::ErrorKind::NotFound.into())).1
+}
+
+#[test]
+fn resolved_names() {
+    assert!(check_string("fn main() { a = 1; b = f(a); return b } fn f() 2").is_empty());
+}
+
+#[test]
+fn unresolved_name() {
+    let errors = check_string("fn main() a + 1");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, codes::UNRESOLVED_NAME);
+    assert_eq!(errors[0].span, 10..11);
+}
+
+#[test]
+fn local_used_before_assignment() {
+    let errors = check_string("fn main() { a = a; a }");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].span, 16..17);
+}
+
+#[test]
+fn locals_are_per_function() {
+    let errors = check_string("fn f() a = 1 fn g() a");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].span, 20..21);
+}
+
+#[test]
+fn duplicate_function() {
+    let errors = check_string("fn f() 1 fn f() 2");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, codes::DUPLICATE_DEFINITION);
+    assert_eq!(errors[0].span, 9..17);
+    assert_eq!(errors[0].labels[0].span, 0..8);
+}
+
+#[test]
+fn parse_errors_come_first() {
+    let errors = check_string("fn main() {a");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, codes::UNEXPECTED_EOF);
+}
+
+#[test]
+fn functions_resolve_across_sources() {
+    let errors = check(&[("a.er", "fn main() f(1)"), ("b.er", "fn f() 2")]);
+    assert_eq!(errors, vec![vec![], vec![]]);
+}
+
+#[test]
+fn diagnostics_per_source() {
+    let errors = check(&[("a.er", "fn main() 1"), ("b.er", "fn main() x")]);
+    assert!(errors[0].is_empty());
+    assert_eq!(errors[1].len(), 2);
+    assert_eq!(errors[1][0].code, codes::DUPLICATE_DEFINITION);
+    assert_eq!(errors[1][0].notes, vec!["first defined in a.er".to_owned()]);
+    assert_eq!(errors[1][1].code, codes::UNRESOLVED_NAME);
+}
+
+#[test]
+fn parse_errors_in_any_source() {
+    let errors = check(&[("a.er", "fn main() f("), ("b.er", "fn f() x")]);
+    assert_eq!(errors[0][0].code, codes::UNEXPECTED_EOF);
+    assert!(errors[1].is_empty());
+}
+
+#[test]
+fn parameters_and_tuple_targets_are_locals() {
+    let source = "fn divmod(a: i32, b: i32) -> (i32, i32) (a / b, a - b)
+        fn main() { (q, r) = divmod(7, 2); q + r }";
+    assert!(check_string(source).is_empty());
+}
+
+#[test]
+fn unknown_type() {
+    let errors = check_string("fn f(a: int) -> (i32, word) a");
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].code, codes::UNKNOWN_TYPE);
+    assert_eq!(errors[0].message, "cannot find type `int`");
+    assert_eq!(errors[1].span, 22..26);
+}
+
+#[test]
+fn fields_through_pointers() {
+    let source = "struct Point { x: f32, y: f32 }
+        struct Line { from: *Point, to: *Point }
+        fn len(l: *Line) -> f32 { l.to.x = l.from.x + 1.0; (l.to).y - l.from.y }";
+    assert!(check_string(source).is_empty());
+}
+
+#[test]
+fn invalid_field_access() {
+    let errors = check_string(
+        "struct Point { x: f32 }
+        fn f(p: *Point, n: i32, q: Point) { p.z + n.x + q.x; a = 1; a.x }",
+    );
+    let found: Vec<(&str, &str)> = errors
+        .iter()
+        .map(|e| (e.code, e.message.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (codes::UNKNOWN_FIELD, "no field `z` on `Point`"),
+            (
+                codes::INVALID_FIELD_ACCESS,
+                "`i32` is not a pointer to a struct"
+            ),
+            (
+                codes::INVALID_FIELD_ACCESS,
+                "`Point` is not a pointer to a struct"
+            ),
+            (
+                codes::INVALID_FIELD_ACCESS,
+                "cannot access field `x` of a value without a declared type"
+            ),
+        ]
+    );
+    assert_eq!(errors[0].span, 70..71);
+}
+
+#[test]
+fn recursive_struct() {
+    let errors = check_string("struct A { b: (i32, B) } struct B { a: A } struct C { c: *C }");
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].code, codes::RECURSIVE_STRUCT);
+    assert_eq!(errors[0].message, "struct `A` contains itself");
+    assert_eq!(errors[0].span, 0..24);
+    assert_eq!(errors[1].message, "struct `B` contains itself");
+}
+
+#[test]
+fn duplicate_structs_and_fields() {
+    let errors = check_string("struct P { x: u8, x: u8 } struct P {}");
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].message, "field `x` is declared more than once");
+    assert_eq!(errors[1].message, "struct `P` is defined more than once");
+}
+
+#[test]
+fn locals_keep_the_type_of_their_value() {
+    let source = "struct P { x: f32, next: *P }
+        fn f(p: *P) { q = p; r = q.next; (s, t) = (r, 1); s.x + q.x }";
+    assert!(check_string(source).is_empty());
+
+    let errors = check_string("struct P { x: f32 } fn f(p: *P) { p = 1; p.x }");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, codes::INVALID_FIELD_ACCESS);
+    assert_eq!(
+        errors[0].message,
+        "cannot access field `x` of a value without a declared type"
+    );
+}
+
+#[test]
+fn fields_of_functions() {
+    let errors = check_string("fn f() f.x fn main() main.y");
+    assert_eq!(errors.len(), 2);
+    assert!(errors.iter().all(|e| e.code == codes::INVALID_FIELD_ACCESS));
+    assert_eq!(errors[0].span, 7..8);
+}
+
+#[test]
+fn tuple_lengths_must_match() {
+    let errors = check_string("fn main() (a, b) = (1, 2, 3)");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, codes::INVALID_ASSIGNMENT);
+    assert_eq!(errors[0].message, "cannot assign 3 values to 2 targets");
+    assert_eq!(errors[0].span, 19..28);
+}
+
+#[test]
+fn duplicate_parameters() {
+    let errors = check_string("fn f(a: i32, a: i32) a");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, codes::DUPLICATE_DEFINITION);
+    assert_eq!(
+        errors[0].message,
+        "parameter `a` is declared more than once"
+    );
+    assert_eq!(errors[0].span, 13..19);
+    assert_eq!(errors[0].labels[0].span, 5..11);
+}
@@ -0,0 +1,56 @@
+use eretria::{parse_string, ExprKind, StatKind};
+
+#[test]
+fn function_span() {
+    let program = parse_string("fn main() 1 + 2").unwrap();
+    assert_eq!(program[0].span, 0..15);
+}
+
+#[test]
+fn data_span() {
+    let program = parse_string(r#"  data[0] = "hi";"#).unwrap();
+    assert_eq!(program[0].span, 2..16);
+}
+
+#[test]
+fn binop_span() {
+    let program = parse_string("fn main() 1 + 2 * 3").unwrap();
+    if let StatKind::Fn { body, .. } = &program[0].kind {
+        assert_eq!(body.span, 10..19);
+        if let ExprKind::BinOp(lhs, _, rhs) = &body.kind {
+            assert_eq!(lhs.span, 10..11);
+            assert_eq!(rhs.span, 14..19);
+            return;
+        }
+    }
+    panic!("unexpected ast");
+}
+
+#[test]
+fn call_span() {
+    let program = parse_string("fn main() main(1, (2))").unwrap();
+    if let StatKind::Fn { body, .. } = &program[0].kind {
+        assert_eq!(body.span, 10..22);
+        if let ExprKind::Call(callee, args) = &body.kind {
+            assert_eq!(callee.span, 10..14);
+            assert_eq!(args[0].span, 15..16);
+            assert_eq!(args[1].span, 18..21);
+            return;
+        }
+    }
+    panic!("unexpected ast");
+}
+
+#[test]
+fn block_span() {
+    let program = parse_string("fn main() {a = 1; return a}").unwrap();
+    if let StatKind::Fn { body, .. } = &program[0].kind {
+        assert_eq!(body.span, 10..27);
+        if let ExprKind::Block(exprs) = &body.kind {
+            assert_eq!(exprs[0].span, 11..16);
+            assert_eq!(exprs[1].span, 18..26);
+            return;
+        }
+    }
+    panic!("unexpected ast");
+}
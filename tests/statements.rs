@@ -1,47 +1,115 @@
-use eretria::parse_string;
-
-#[test]
-fn statements_no_semi() {
-    // without semicolons
-    assert!(parse_string(
-        &r#"
-	data[0] = "hi" 
-	fn main() {2} 
-	fn main2() 1 + 1
-	"#,
-    )
-    .is_ok());
-}
-
-#[test]
-fn statements_semi() {
-    // with semicolons
-    assert!(parse_string(
-        r#"
-	;;;data[0] = "hi";;;;;  ;;
-	;;fn main() {2}; ;;
-	;;;fn main2() 1 + 1;;
-	"#,
-    )
-    .is_ok());
-}
-
-#[test]
-fn unexpected_token() {
-    assert!(parse_string("ssdjfhksjdggr").is_err());
-}
-
-#[test]
-fn invalid_data_syntax() {
-    assert!(parse_string("data[word] = \"hi\"").is_err());
-}
-
-#[test]
-fn data_decimal_escape() {
-    assert!(parse_string(r#"data[0] = "\d255\d128""#).is_ok());
-}
-
-#[test]
-fn data_hex_escape() {
-    assert!(parse_string(r#"data[0] = "\xFF\xDD""#).is_ok());
-}
+use eretria::{ast::TypeKind, parse_string, StatKind};
+
+#[test]
+fn statements_no_semi() {
+    // without semicolons
+    assert!(parse_string(
+        r#"
+	data[0] = "hi" 
+	fn main() {2} 
+	fn main2() 1 + 1
+	"#,
+    )
+    .is_ok());
+}
+
+#[test]
+fn statements_semi() {
+    // with semicolons
+    assert!(parse_string(
+        r#"
+	;;;data[0] = "hi";;;;;  ;;
+	;;fn main() {2}; ;;
+	;;;fn main2() 1 + 1;;
+	"#,
+    )
+    .is_ok());
+}
+
+#[test]
+fn unexpected_token() {
+    assert!(parse_string("ssdjfhksjdggr").is_err());
+}
+
+#[test]
+fn invalid_data_syntax() {
+    assert!(parse_string("data[word] = \"hi\"").is_err());
+}
+
+#[test]
+fn data_decimal_escape() {
+    assert!(parse_string(r#"data[0] = "\d255\d128""#).is_ok());
+}
+
+#[test]
+fn data_hex_escape() {
+    assert!(parse_string(r#"data[0] = "\xFF\xDD""#).is_ok());
+}
+
+#[test]
+fn negative_data_offset() {
+    assert!(parse_string(r#"data[-1] = "hi""#).is_err());
+}
+
+#[test]
+fn data_non_string() {
+    assert!(parse_string("data[0] = 1").is_err());
+}
+
+#[test]
+fn function_signature() {
+    let program = parse_string("fn divmod(a: i32, b: i32) -> (i32, i32) (a / b, a - b)").unwrap();
+    match &program[0].kind {
+        StatKind::Fn {
+            params,
+            results: Some(results),
+            ..
+        } => {
+            let names: Vec<_> = params.iter().map(|p| p.name.as_str()).collect();
+            assert_eq!(names, ["a", "b"]);
+            assert!(matches!(&results.kind, TypeKind::Tuple(types) if types.len() == 2));
+        }
+        kind => panic!("expected a function with results, got {:?}", kind),
+    }
+}
+
+#[test]
+fn parameter_without_type() {
+    assert!(parse_string("fn f(a) a").is_err());
+    assert!(parse_string("fn f(a: i32,) a").is_err());
+    assert!(parse_string("fn f() -> (i32 1").is_err());
+}
+
+#[test]
+fn struct_declaration() {
+    let program = parse_string("struct Point { x: f32, y: *Point, }").unwrap();
+    match &program[0].kind {
+        StatKind::Struct { name, fields } => {
+            assert_eq!(name, "Point");
+            let names: Vec<_> = fields.iter().map(|f| f.name.as_str()).collect();
+            assert_eq!(names, ["x", "y"]);
+            assert!(matches!(&fields[1].ty.kind, TypeKind::Pointer(_)));
+            assert_eq!(fields[1].span, 23..32);
+        }
+        kind => panic!("expected a struct, got {:?}", kind),
+    }
+    assert!(parse_string("struct P { x }").is_err());
+    assert!(parse_string("struct { x: u8 }").is_err());
+}
+
+#[test]
+fn parenthesised_type() {
+    let program = parse_string("fn f(a: (*(i32))) -> (i32) a").unwrap();
+    match &program[0].kind {
+        StatKind::Fn {
+            params,
+            results: Some(results),
+            ..
+        } => {
+            assert_eq!(results.kind, TypeKind::Name("i32".to_owned()));
+            assert_eq!(results.span, 22..25);
+            assert_eq!(params[0].ty.to_string(), "*i32");
+        }
+        kind => panic!("expected a function with results, got {:?}", kind),
+    }
+}
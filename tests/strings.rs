@@ -0,0 +1,127 @@
+use eretria::{
+    lexer::{tokenize, LexErrorKind, Token},
+    parse_string, ExprKind, StatKind,
+};
+
+fn token(source: &str) -> Result<Token, LexErrorKind> {
+    tokenize(source)
+        .next()
+        .unwrap()
+        .map(|(t, _)| t)
+        .map_err(|e| e.kind)
+}
+
+fn string(source: &str) -> String {
+    match token(source) {
+        Ok(Token::String(s)) => s,
+        t => panic!("expected string, got {:?}", t),
+    }
+}
+
+fn bytes(source: &str) -> Vec<u8> {
+    match token(source) {
+        Ok(Token::ByteString(b)) => b,
+        t => panic!("expected byte string, got {:?}", t),
+    }
+}
+
+#[test]
+fn standard_escapes() {
+    assert_eq!(string(r#""\n\r\t\0\\\"\'""#), "\n\r\t\0\\\"'");
+}
+
+#[test]
+fn unicode_escape() {
+    assert_eq!(string(r#""\u{41}\u{1F600}""#), "A\u{1F600}");
+}
+
+#[test]
+fn invalid_unicode_escape() {
+    assert_eq!(token(r#""\u41""#), Err(LexErrorKind::InvalidEscape));
+    assert_eq!(token(r#""\u{}""#), Err(LexErrorKind::InvalidEscape));
+    assert_eq!(token(r#""\u{D800}""#), Err(LexErrorKind::InvalidEscape));
+    assert_eq!(token(r#""\u{1234567}""#), Err(LexErrorKind::InvalidEscape));
+}
+
+#[test]
+fn unknown_escape() {
+    assert_eq!(token(r#""\k""#), Err(LexErrorKind::InvalidEscape));
+    assert_eq!(token(r#""\x""#), Err(LexErrorKind::InvalidEscape));
+}
+
+#[test]
+fn radix_escapes() {
+    assert_eq!(string(r#""\x6e\d71\b1000001""#), "nGA");
+}
+
+#[test]
+fn tab_is_never_numeric() {
+    assert_eq!(string(r#""\t1\t0""#), "\t1\t0");
+}
+
+#[test]
+fn numeric_escape_is_codepoint_in_strings() {
+    assert_eq!(string(r#""\xFF""#).into_bytes(), vec![0xC3, 0xBF]);
+}
+
+#[test]
+fn byte_string() {
+    assert_eq!(bytes(r#"b"\xFF\x00z\n""#), vec![0xFF, 0x00, b'z', b'\n']);
+    assert_eq!(bytes(r#"b"é""#), "é".as_bytes());
+}
+
+#[test]
+fn byte_string_escape_out_of_range() {
+    assert_eq!(token(r#"b"\x100""#), Err(LexErrorKind::InvalidEscape));
+}
+
+#[test]
+fn data_bytes() {
+    let program = parse_string(r#"data[0] = b"\xFF\xDD"; data[2] = "\xFF""#).unwrap();
+    match (&program[0].kind, &program[1].kind) {
+        (
+            StatKind::Data {
+                offset: 0,
+                bytes: a,
+            },
+            StatKind::Data {
+                offset: 2,
+                bytes: b,
+            },
+        ) => {
+            assert_eq!(a, &vec![0xFF, 0xDD]);
+            assert_eq!(b, &vec![0xC3, 0xBF]);
+        }
+        _ => panic!("expected data statements"),
+    }
+}
+
+#[test]
+fn char_literals() {
+    assert_eq!(token("'a'"), Ok(Token::Char('a')));
+    assert_eq!(token(r"'\n'"), Ok(Token::Char('\n')));
+    assert_eq!(token(r"'\x41'"), Ok(Token::Char('A')));
+    assert_eq!(token(r"'\''"), Ok(Token::Char('\'')));
+    assert_eq!(token("'é'"), Ok(Token::Char('é')));
+}
+
+#[test]
+fn invalid_char_literals() {
+    assert_eq!(token("''"), Err(LexErrorKind::InvalidCharLiteral));
+    assert_eq!(token("'ab'"), Err(LexErrorKind::InvalidCharLiteral));
+    assert_eq!(token(r"'\q'"), Err(LexErrorKind::InvalidEscape));
+    assert_eq!(token("'a"), Err(LexErrorKind::UnterminatedString));
+}
+
+#[test]
+fn char_expression() {
+    let program = parse_string("fn main() c == '\\n'").unwrap();
+    if let StatKind::Fn { body, .. } = &program[0].kind {
+        if let ExprKind::BinOp(_, _, rhs) = &body.kind {
+            assert!(matches!(rhs.kind, ExprKind::Char('\n')));
+            assert_eq!(rhs.span, 15..19);
+            return;
+        }
+    }
+    panic!("unexpected ast");
+}
@@ -0,0 +1,84 @@
+use eretria::{
+    lexer::Number,
+    parse_string,
+    visit::{self, Fold, Visitor, VisitorMut},
+    Expr, ExprKind, StatKind,
+};
+
+#[derive(Default)]
+struct Idents(Vec<String>);
+
+impl Visitor for Idents {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let ExprKind::Ident(name) = &expr.kind {
+            self.0.push(name.clone());
+        }
+        visit::walk_expr(self, expr);
+    }
+}
+
+#[test]
+fn visitor_reaches_every_expression() {
+    let program = parse_string("fn main() { a = f(b, (c)); return d + e }").unwrap();
+    let mut idents = Idents::default();
+    idents.visit_program(&program);
+    assert_eq!(idents.0, vec!["a", "f", "b", "c", "d", "e"]);
+}
+
+struct Rename;
+
+impl VisitorMut for Rename {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if let ExprKind::Ident(name) = &mut expr.kind {
+            name.insert(0, '_');
+        }
+        visit::walk_expr_mut(self, expr);
+    }
+}
+
+#[test]
+fn visitor_mut_edits_in_place() {
+    let mut program = parse_string("fn main() f(x) * y").unwrap();
+    Rename.visit_program_mut(&mut program);
+    let mut idents = Idents::default();
+    idents.visit_program(&program);
+    assert_eq!(idents.0, vec!["_f", "_x", "_y"]);
+}
+
+/// Folds additions of two unsuffixed integers.
+struct ConstFold;
+
+impl Fold for ConstFold {
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        let expr = visit::fold_expr(self, expr);
+        if let ExprKind::BinOp(lhs, op, rhs) = &expr.kind {
+            if let (ExprKind::Integer(a), "+", ExprKind::Integer(b)) =
+                (&lhs.kind, &op[..], &rhs.kind)
+            {
+                let value = a.value + b.value;
+                return Expr {
+                    kind: ExprKind::Integer(Number { value, ty: None }),
+                    span: expr.span,
+                };
+            }
+        }
+        expr
+    }
+}
+
+#[test]
+fn fold_replaces_nodes() {
+    let program = parse_string("fn main() g(1 + 2 + 3)").unwrap();
+    let program = ConstFold.fold_program(program);
+    if let StatKind::Fn { body, .. } = &program[0].kind {
+        if let ExprKind::Call(_, args) = &body.kind {
+            assert!(matches!(
+                args[0].kind,
+                ExprKind::Integer(Number { value: 6, .. })
+            ));
+            assert_eq!(args[0].span, 12..21);
+            return;
+        }
+    }
+    panic!("unexpected ast");
+}